@@ -1,17 +1,19 @@
-#![feature(test)]
+use criterion::{criterion_group, criterion_main, Criterion};
 
-#[cfg(test)]
-mod tests {
-    extern crate test;
-    use test::Bencher;
+const VCF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/benches/data/variation.vcf");
 
-    use flex_vg;
+fn bench_handle_vcf(c: &mut Criterion) {
+    // Fail the bench on bad input rather than timing the error
+    flex_vg::gen_variations(&mut flex_vg::open_vcf(VCF).unwrap()).unwrap();
 
-    #[bench]
-    fn bench_handle_vcf(b: &mut Bencher) {
-        let mut vcf_reader = flex_vg::open_vcf(
-            "/Users/mmwaniki/data/mouse_mm10/C57BL/4512-JFI-0333_C57BL_6J_two_lanes_large_svs.vcf",
-        );
-        b.iter(|| flex_vg::gen_variations(&mut vcf_reader));
-    }
+    // The records are consumed by each iteration so the reader is opened in it
+    c.bench_function("handle_vcf", |b| {
+        b.iter(|| -> Result<_, flex_vg::Error> {
+            let mut vcf_reader = flex_vg::open_vcf(VCF)?;
+            flex_vg::gen_variations(&mut vcf_reader)
+        })
+    });
 }
+
+criterion_group!(benches, bench_handle_vcf);
+criterion_main!(benches);
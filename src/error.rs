@@ -0,0 +1,119 @@
+//! Errors returned by flex_vg
+
+use std::fmt;
+use std::io;
+use std::str::Utf8Error;
+
+use crate::graph::types::NodeId;
+
+#[derive(Debug)]
+pub enum Error {
+    // A node that isn't in the graph
+    MissingNode(NodeId),
+
+    // A path that isn't in the graph
+    MissingPath(String),
+
+    // A path with the same name is already in the graph
+    PathExists(String),
+
+    // A sequence (chromosome/contig) that isn't in the reference or graph
+    UnknownContig(String),
+
+    // A sample that isn't in the VCF
+    UnknownSample(String),
+
+    // A position that is outside of the sequence it's on. 1 based like VCF positions.
+    OutOfRange {
+        contig: String,
+        position: u64,
+    },
+
+    // The reference allele of a VCF record doesn't match the reference. 1 based like VCF positions.
+    RefMismatch {
+        contig: String,
+        position: u64,
+        expected: String,
+        found: String,
+    },
+
+    // Input that we couldn't parse
+    Malformed(String),
+
+    // Valid input that we can't represent yet
+    Unsupported(String),
+
+    // Reading or writing failed
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingNode(id) => write!(f, "Node {} isn't in the graph", id),
+            Error::MissingPath(name) => write!(f, "Path {} isn't in the graph", name),
+            Error::PathExists(name) => write!(f, "Path {} is already in the graph", name),
+            Error::UnknownContig(contig) => write!(f, "Couldn't find {} in the reference", contig),
+            Error::UnknownSample(sample) => write!(f, "Sample {} isn't in the VCF", sample),
+            Error::OutOfRange { contig, position } => {
+                write!(f, "Position {} is outside of {}", position, contig)
+            }
+            Error::RefMismatch {
+                contig,
+                position,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Reference allele {} at {}:{} doesn't match the reference {}",
+                found, contig, position, expected
+            ),
+            Error::Malformed(msg) => write!(f, "Malformed input: {}", msg),
+            Error::Unsupported(msg) => write!(f, "Unsupported input: {}", msg),
+            Error::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<Utf8Error> for Error {
+    fn from(e: Utf8Error) -> Self {
+        Error::Malformed(e.to_string())
+    }
+}
+
+impl From<vcf::VCFParseError> for Error {
+    fn from(e: vcf::VCFParseError) -> Self {
+        Error::Malformed(e.to_string())
+    }
+}
+
+impl From<serde_cbor::Error> for Error {
+    fn from(e: serde_cbor::Error) -> Self {
+        if e.is_io() {
+            Error::Io(io::Error::other(e))
+        } else {
+            Error::Malformed(e.to_string())
+        }
+    }
+}
+
+impl From<needletail::ParseError> for Error {
+    fn from(e: needletail::ParseError) -> Self {
+        Error::Malformed(e.to_string())
+    }
+}
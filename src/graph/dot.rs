@@ -1,19 +1,22 @@
-use crate::graph::types::{Graph, Node};
+use crate::graph::types::{Edge, Graph};
 use std::env;
-use std::fs::File;
-use std::io::Write;
+use std::io::{self, Write};
 
 const NAME: &str = env!("CARGO_PKG_NAME");
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-fn fmt_graph_to_dot(g: &Graph) {
-    let hm = g.hashmap();
+// Longest part of a segment to show in a node label
+const LABEL_LENGTH: usize = 5;
 
-    let mut file = File::create("foo.dot").unwrap();
+/// Write the graph in the [DOT] graph description language
+///
+/// [DOT]: https://graphviz.org/doc/info/lang.html
+pub fn fmt_graph_to_dot<W: Write>(g: &Graph, file: &mut W) -> io::Result<()> {
+    let hm = g.hashmap();
 
     // Write header
     writeln!(
-        &mut file,
+        file,
         "\
 digraph {{
 \t// Generated by {} version {}
@@ -24,32 +27,42 @@ digraph {{
 \tnode [shape=box];
 ",
         NAME, VERSION
-    )
-    .unwrap();
+    )?;
 
     // Write nodes
-    writeln!(&mut file, "\t// nodes").unwrap();
+    writeln!(file, "\t// nodes")?;
 
     for (k, v) in hm.iter() {
-        writeln!(&mut file, "\t{} [label=\"{}\"]", k, &v.segment[..5]).unwrap();
+        let label: String = v.segment.chars().take(LABEL_LENGTH).collect();
+        writeln!(file, "\t\"{}\" [label=\"{}\"]", k, label)?;
     }
 
     // write edges
-    writeln!(&mut file, "\n\t// edges").unwrap();
+    writeln!(file, "\n\t// edges")?;
+
+    // Edges that leave or enter a node on its reverse strand use its left or right side
+    for Edge(x, y) in g.edges() {
+        write!(file, "\t\"{}\" -> \"{}\"", x.id(), y.id())?;
 
-    for (k, Node { nodes_right, .. }) in hm.iter() {
-        for n in nodes_right.iter() {
-            writeln!(&mut file, "\t{} -> {}", k, n).unwrap();
+        match (x.is_reverse(), y.is_reverse()) {
+            (false, false) => writeln!(file)?,
+            (x_reverse, y_reverse) => writeln!(
+                file,
+                " [tailport={}, headport={}]",
+                if x_reverse { "sw" } else { "ne" },
+                if y_reverse { "se" } else { "nw" }
+            )?,
         }
     }
 
-    writeln!(&mut file, "}}").unwrap();
+    writeln!(file, "}}")
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::graph::types::{Handle, Node};
     //use crate::graph::macros;
 
     const RAW_SEQ: &str = "ACTGATGATCTGATCGGATA";
@@ -57,6 +70,45 @@ mod tests {
     const OFFSET: usize = 23;
 
     #[test]
-    #[ignore]
-    fn test_digraph_to_dot() {}
+    fn test_digraph_to_dot() {
+        let x = Node::new(RAW_SEQ, OFFSET, RAW_REF, Vec::new(), Vec::new());
+        let y = Node::new(
+            "AC",
+            OFFSET + RAW_SEQ.len(),
+            RAW_REF,
+            Vec::new(),
+            Vec::new(),
+        );
+        let mut g = Graph::new();
+        let (x_id, y_id) = (g.add_node(x), g.add_node(y));
+        g.add_edge_from_id(x_id, y_id).unwrap();
+
+        let mut out: Vec<u8> = Vec::new();
+        fmt_graph_to_dot(&g, &mut out).unwrap();
+        let dot = String::from_utf8(out).unwrap();
+
+        assert!(dot.starts_with("digraph {"));
+        assert!(dot.contains(&format!("\"{}\" [label=\"ACTGA\"]", x_id)));
+        assert!(dot.contains(&format!("\"{}\" [label=\"AC\"]", y_id)));
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\"\n", x_id, y_id)));
+        assert!(dot.trim_end().ends_with('}'));
+    }
+
+    #[test]
+    fn test_inversion_to_dot() {
+        let x = Node::new(RAW_SEQ, OFFSET, RAW_REF, Vec::new(), Vec::new());
+        let y = Node::alternative("AC", OFFSET + RAW_SEQ.len(), Vec::new(), Vec::new());
+        let mut g = Graph::new();
+        let (x_id, y_id) = (g.add_node(x), g.add_node(y));
+        g.add_edge(Handle::forward(x_id), Handle::reverse(y_id))
+            .unwrap();
+
+        let mut out: Vec<u8> = Vec::new();
+        fmt_graph_to_dot(&g, &mut out).unwrap();
+        let dot = String::from_utf8(out).unwrap();
+        let edges: Vec<&str> = dot.lines().filter(|l| l.contains("->")).collect();
+
+        assert_eq!(edges.len(), 1);
+        assert!(edges[0].ends_with("[tailport=ne, headport=se]"));
+    }
 }
@@ -0,0 +1,864 @@
+//! [GFA 1.0] import and export
+//!
+//! [GFA 1.0]: https://github.com/GFA-spec/GFA-spec/blob/master/GFA1.md
+
+use crate::error::Error;
+use crate::graph::types::{Edge, Graph, Handle, HashScheme, Node, NodeHash, NodeId, Orientation};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, Write};
+
+const VERSION: &str = "1.0";
+
+// The name of the tag that holds the hash of a segment
+const HASH_TAG: &str = "sh";
+
+// The name of the header tag that holds the scheme the segments are hashed with
+const SCHEME_TAG: &str = "hs";
+
+// The names of the tags that hold the reference and offset of a segment as in rGFA
+const REFERENCE_TAG: &str = "SN";
+const OFFSET_TAG: &str = "SO";
+
+/// An optional field of a segment e.g. `LN:i:4`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Tag {
+    pub name: String,
+    pub kind: char,
+    pub value: String,
+}
+
+/// A graph read from GFA along with what the GFA holds that the graph doesn't
+pub struct Gfa {
+    pub graph: Graph,
+
+    // The id of each segment keyed by the name of the segment
+    pub segments: HashMap<String, NodeId>,
+
+    // The optional tags of each segment that has any
+    pub tags: HashMap<NodeId, Vec<Tag>>,
+}
+
+// An `S` line
+struct Segment {
+    name: String,
+    sequence: String,
+    hash: Option<NodeHash>,
+    // The reference and offset from its tags
+    position: Option<(String, usize)>,
+    tags: Vec<Tag>,
+}
+
+// An `L` line before we know the ids of its segments
+struct Link {
+    line_number: usize,
+    from: (String, Orientation),
+    to: (String, Orientation),
+}
+
+// A `P` or `W` line before we know the ids of its segments
+struct Walk {
+    line_number: usize,
+    name: String,
+    start: usize,
+    // The name of each segment and whether it's visited in reverse
+    steps: Vec<(String, bool)>,
+}
+
+/// Write the graph in GFA 1.0
+///
+///  - an `H` line with the hash scheme of the graph in an `hs:Z` tag
+///  - an `S` line for each node named by its id with its hash in an `sh:Z` tag
+///    and, when it's on a reference, the reference and its offset on it in `SN:Z` and `SO:i` tags
+///  - an `L` line for each edge
+///  - a `W` line for each path of a haplotype, named `sample#haplotype#sequence` as in [PanSN]
+///    with the offset it starts at after it when that isn't 0 e.g. `HG002#1#chr1[120]`
+///  - a `P` line for each other path of the graph
+///
+/// [PanSN]: https://github.com/pangenome/PanSN-spec
+pub fn fmt_graph_to_gfa<W: Write>(g: &Graph, file: &mut W) -> io::Result<()> {
+    // Sort the nodes so that the same graph is always written out the same way
+    let mut nodes: Vec<&Node> = g.hashmap().values().collect();
+    nodes.sort_by_key(|n| n.id);
+
+    // Write header
+    writeln!(file, "H\tVN:Z:{}\t{}:Z:{}", VERSION, SCHEME_TAG, g.scheme())?;
+
+    // Write segments
+    for n in nodes.iter() {
+        write!(file, "S\t{}\t{}", n.id, n.segment)?;
+        if let Some(hash) = n.hash() {
+            write!(file, "\t{}:Z:{}", HASH_TAG, hash)?;
+        }
+        if let Some(reference) = n.reference() {
+            write!(
+                file,
+                "\t{}:Z:{}\t{}:i:{}",
+                REFERENCE_TAG,
+                reference,
+                OFFSET_TAG,
+                n.offset()
+            )?;
+        }
+        writeln!(file)?;
+    }
+
+    // Write links.
+    // Each edge is on both of its nodes so only write it from the node that starts its canonical form.
+    let mut seen: HashSet<Edge> = HashSet::new();
+
+    for n in nodes.iter() {
+        for e in n.edges() {
+            if e == e.canonical() && seen.insert(e) {
+                let Edge(x, y) = e;
+                writeln!(
+                    file,
+                    "L\t{}\t{}\t{}\t{}\t0M",
+                    x.id(),
+                    strand(x),
+                    y.id(),
+                    strand(y)
+                )?;
+            }
+        }
+    }
+
+    // Write paths
+    for (name, path) in g.paths() {
+        match pansn(name) {
+            Some((sample, haplotype, sequence, start)) => {
+                let mut walk = String::new();
+                let mut end = start;
+                for h in path {
+                    walk.push(if h.is_reverse() { '<' } else { '>' });
+                    walk.push_str(&h.id().to_string());
+                    end += g.get_node(h.id()).map_or(0, |n| n.segment.len());
+                }
+
+                writeln!(
+                    file,
+                    "W\t{}\t{}\t{}\t{}\t{}\t{}",
+                    sample, haplotype, sequence, start, end, walk
+                )?;
+            }
+            None => {
+                let steps: Vec<String> = path.iter().map(|h| h.to_string()).collect();
+                writeln!(file, "P\t{}\t{}\t*", name, steps.join(","))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Read a graph from GFA 1.0
+///
+/// Segments named with numeric ids, as written by [`fmt_graph_to_gfa`] and vg, keep their ids.
+/// Other segments get ids that follow the largest of them in the order they're in the file.
+/// A segment gets its hash from an `sh:Z` tag or from its name when it's named by a hash.
+/// The hashes are taken to be in the scheme of the `hs:Z` header tag or the default one.
+///
+/// A segment gets its reference and offset from `SN:Z` and `SO:i` tags.
+///
+/// `P` and `W` lines become paths of the graph.
+/// `W` lines are named `sample#haplotype#sequence` as in [PanSN],
+/// with `[start]` after it when the walk doesn't start at 0 like [`fmt_graph_to_gfa`] writes them.
+/// A segment without the tags gets its reference and offset from the first path to visit it,
+/// paths of reference sequences before those of haplotypes.
+///
+/// [PanSN]: https://github.com/pangenome/PanSN-spec
+///
+/// [`fmt_graph_to_gfa`]: fn.fmt_graph_to_gfa.html
+pub fn parse_gfa<R: BufRead>(reader: R) -> Result<Gfa, Error> {
+    let mut scheme = HashScheme::default();
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut links: Vec<Link> = Vec::new();
+    let mut walks: Vec<Walk> = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = index + 1;
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        let expect_fields = |count: usize| {
+            if fields.len() < count {
+                Err(malformed(
+                    line_number,
+                    format!("expected at least {} fields", count),
+                ))
+            } else {
+                Ok(())
+            }
+        };
+
+        match fields[0] {
+            "H" => {
+                for field in fields[1..].iter() {
+                    let tag = parse_tag(line_number, field)?;
+
+                    if tag.name == SCHEME_TAG {
+                        scheme = tag.value.parse().map_err(|_| {
+                            malformed(line_number, format!("bad hash scheme {}", tag.value))
+                        })?;
+                    }
+                }
+            }
+            "S" => {
+                expect_fields(3)?;
+                if fields[2] == "*" {
+                    return Err(Error::Unsupported(format!(
+                        "line {}: segment {} has no sequence",
+                        line_number, fields[1]
+                    )));
+                }
+
+                let mut tags: Vec<Tag> = fields[3..]
+                    .iter()
+                    .map(|t| parse_tag(line_number, t))
+                    .collect::<Result<_, _>>()?;
+
+                // The hash is kept on the node rather than with the other tags
+                let mut hash = fields[1].parse::<NodeHash>().ok();
+                if let Some(tag) = take_tag(&mut tags, HASH_TAG) {
+                    let parsed = tag
+                        .value
+                        .parse()
+                        .map_err(|_| malformed(line_number, format!("bad hash {}", tag.value)))?;
+                    hash = Some(parsed);
+                }
+
+                // So are the reference and offset
+                let offset = match take_tag(&mut tags, OFFSET_TAG) {
+                    Some(tag) => tag
+                        .value
+                        .parse()
+                        .map_err(|_| malformed(line_number, format!("bad offset {}", tag.value)))?,
+                    None => 0,
+                };
+                let position = take_tag(&mut tags, REFERENCE_TAG).map(|tag| (tag.value, offset));
+
+                segments.push(Segment {
+                    name: fields[1].to_owned(),
+                    sequence: fields[2].to_owned(),
+                    hash,
+                    position,
+                    tags,
+                });
+            }
+            "L" => {
+                expect_fields(6)?;
+                if fields[5] != "0M" && fields[5] != "*" {
+                    return Err(Error::Unsupported(format!(
+                        "line {}: overlap {}",
+                        line_number, fields[5]
+                    )));
+                }
+
+                let from = (fields[1].to_owned(), orientation(line_number, fields[2])?);
+                let to = (fields[3].to_owned(), orientation(line_number, fields[4])?);
+                links.push(Link {
+                    line_number,
+                    from,
+                    to,
+                });
+            }
+            "P" => {
+                expect_fields(3)?;
+                let steps = fields[2]
+                    .split(',')
+                    .map(|step| match step.split_at(step.len().saturating_sub(1)) {
+                        (name, "+") if !name.is_empty() => Ok((name.to_owned(), false)),
+                        (name, "-") if !name.is_empty() => Ok((name.to_owned(), true)),
+                        _ => Err(malformed(line_number, format!("bad path step {}", step))),
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                walks.push(Walk {
+                    line_number,
+                    name: fields[1].to_owned(),
+                    start: 0,
+                    steps,
+                });
+            }
+            "W" => {
+                expect_fields(7)?;
+                let start = match fields[4] {
+                    "*" => 0,
+                    start => start
+                        .parse()
+                        .map_err(|_| malformed(line_number, format!("bad start {}", start)))?,
+                };
+
+                let mut name = format!("{}#{}#{}", fields[1], fields[2], fields[3]);
+                if start != 0 {
+                    name = format!("{}[{}]", name, start);
+                }
+
+                walks.push(Walk {
+                    line_number,
+                    name,
+                    start,
+                    steps: parse_walk(line_number, fields[6])?,
+                });
+            }
+            // Headers, containments and anything else carry nothing we keep
+            _ => {}
+        }
+    }
+
+    // Give each segment an id
+    let mut ids: HashMap<String, NodeId> = HashMap::new();
+    let mut lengths: HashMap<NodeId, usize> = HashMap::new();
+
+    let numbered: Vec<Option<NodeId>> = segments
+        .iter()
+        .map(|s| s.name.parse::<NodeId>().ok())
+        .collect();
+    let mut next_id = numbered
+        .iter()
+        .flatten()
+        .map(|id| id.value())
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    for (segment, numbered) in segments.iter().zip(numbered) {
+        let id = match numbered {
+            Some(id) => id,
+            None => {
+                next_id += 1;
+                NodeId::new(next_id - 1)
+            }
+        };
+
+        if ids.insert(segment.name.clone(), id).is_some()
+            || lengths.insert(id, segment.sequence.len()).is_some()
+        {
+            return Err(Error::Malformed(format!(
+                "duplicate segment {}",
+                segment.name
+            )));
+        }
+    }
+
+    let find = |line_number: usize, name: &str| match ids.get(name) {
+        Some(id) => Ok(*id),
+        None => Err(malformed(line_number, format!("unknown segment {}", name))),
+    };
+
+    let mut paths: Vec<(usize, String, Vec<Handle>)> = Vec::new();
+
+    for walk in walks.iter() {
+        let mut path: Vec<Handle> = Vec::new();
+
+        for (segment, is_reverse) in walk.steps.iter() {
+            let id = find(walk.line_number, segment)?;
+
+            if *is_reverse {
+                path.push(Handle::reverse(id));
+            } else {
+                path.push(Handle::forward(id));
+            }
+        }
+
+        paths.push((walk.line_number, walk.name.clone(), path));
+    }
+
+    // Walk the paths to find the reference and offset of the nodes on them that have no tags.
+    // The paths of haplotypes are PanSN names and go after those of the reference.
+    let mut positions: HashMap<NodeId, (String, usize)> = HashMap::new();
+    let is_haplotype = |walk: &&Walk| walk.name.contains('#');
+
+    for walk in walks
+        .iter()
+        .filter(|walk| !is_haplotype(walk))
+        .chain(walks.iter().filter(is_haplotype))
+    {
+        let mut offset = walk.start;
+
+        for (segment, _) in walk.steps.iter() {
+            let id = ids[segment];
+            positions
+                .entry(id)
+                .or_insert_with(|| (walk.name.clone(), offset));
+            offset += lengths[&id];
+        }
+    }
+
+    // Build the graph
+    let mut graph = Graph::with_scheme(scheme);
+    let mut tags: HashMap<NodeId, Vec<Tag>> = HashMap::new();
+
+    for segment in segments {
+        let id = ids[&segment.name];
+        let (reference, offset) = match segment.position.or_else(|| positions.remove(&id)) {
+            Some((reference, offset)) => (Some(reference), offset),
+            None => (None, 0),
+        };
+
+        let mut n = Node::with_hash(
+            segment.hash,
+            segment.sequence,
+            offset,
+            reference,
+            Vec::new(),
+            Vec::new(),
+        );
+        n.id = id;
+        graph.insert_node(n)?;
+
+        if !segment.tags.is_empty() {
+            tags.insert(id, segment.tags);
+        }
+    }
+
+    for link in links {
+        let x = Handle::new(find(link.line_number, &link.from.0)?, link.from.1);
+        let y = Handle::new(find(link.line_number, &link.to.0)?, link.to.1);
+
+        if !graph.has_edge(x, y)? {
+            graph.add_edge(x, y)?;
+        }
+    }
+
+    for (line_number, name, path) in paths {
+        graph
+            .add_path(&name)
+            .map_err(|e| malformed(line_number, e.to_string()))?;
+
+        for h in path {
+            graph.append_step(&name, h)?;
+        }
+    }
+
+    Ok(Gfa {
+        graph,
+        segments: ids,
+        tags,
+    })
+}
+
+fn malformed<S: AsRef<str>>(line_number: usize, msg: S) -> Error {
+    Error::Malformed(format!("line {}: {}", line_number, msg.as_ref()))
+}
+
+// The orientation field of an `L` line
+fn orientation(line_number: usize, field: &str) -> Result<Orientation, Error> {
+    match field {
+        "+" => Ok(Orientation::Forward),
+        "-" => Ok(Orientation::Reverse),
+        _ => Err(malformed(line_number, "orientation must be + or -")),
+    }
+}
+
+// The sample, haplotype, sequence and start of a path named as in PanSN
+// e.g. `HG002#1#chr1` or `HG002#1#chr1[120]`
+fn pansn(name: &str) -> Option<(&str, usize, &str, usize)> {
+    let mut parts = name.splitn(3, '#');
+    let (sample, haplotype, sequence) = (parts.next()?, parts.next()?, parts.next()?);
+    let haplotype = haplotype.parse().ok()?;
+
+    if sample.is_empty() || sequence.is_empty() {
+        return None;
+    }
+
+    let start = sequence
+        .strip_suffix(']')
+        .and_then(|rest| rest.rsplit_once('['))
+        .and_then(|(sequence, start)| Some((sequence, start.parse().ok()?)));
+
+    match start {
+        Some((sequence, start)) => Some((sample, haplotype, sequence, start)),
+        None => Some((sample, haplotype, sequence, 0)),
+    }
+}
+
+fn strand(h: Handle) -> char {
+    if h.is_reverse() {
+        '-'
+    } else {
+        '+'
+    }
+}
+
+// Take the tag named `name` out of `tags`
+fn take_tag(tags: &mut Vec<Tag>, name: &str) -> Option<Tag> {
+    let index = tags.iter().position(|t| t.name == name)?;
+    Some(tags.remove(index))
+}
+
+// Parse an optional field in the format `TAG:TYPE:VALUE`
+fn parse_tag(line_number: usize, field: &str) -> Result<Tag, Error> {
+    let parts: Vec<&str> = field.splitn(3, ':').collect();
+
+    match parts[..] {
+        [name, kind, value] if name.len() == 2 && kind.len() == 1 => Ok(Tag {
+            name: name.to_owned(),
+            kind: kind.chars().next().unwrap(),
+            value: value.to_owned(),
+        }),
+        _ => Err(malformed(line_number, format!("bad tag {}", field))),
+    }
+}
+
+// Parse the walk of a `W` line e.g. `>s1<s2>s3`
+fn parse_walk(line_number: usize, walk: &str) -> Result<Vec<(String, bool)>, Error> {
+    let mut steps: Vec<(String, bool)> = Vec::new();
+
+    for c in walk.chars() {
+        match c {
+            '>' => steps.push((String::new(), false)),
+            '<' => steps.push((String::new(), true)),
+            _ => match steps.last_mut() {
+                Some((name, _)) => name.push(c),
+                None => return Err(malformed(line_number, format!("bad walk {}", walk))),
+            },
+        }
+    }
+
+    if steps.iter().any(|(name, _)| name.is_empty()) {
+        return Err(malformed(line_number, format!("bad walk {}", walk)));
+    }
+
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::graph::ConstructOptions;
+    use crate::graph::update;
+    use crate::io::reference;
+    use crate::io::variation::{RefCheck, Unphased};
+    use std::collections::BTreeMap;
+    use std::io::Cursor;
+    use vcf::VCFReader;
+
+    fn yeild_graph() -> Graph {
+        let fasta = Cursor::new(">chr1\nGATCATCGGTTACACAGCTA\n");
+        let vcf = Cursor::new(
+            "\
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+chr1	5	rs21549	A	G	.	PASS	.
+",
+        );
+
+        reference::construct(fasta, vcf).unwrap()
+    }
+
+    // The ids of the nodes in the graph keyed by segment
+    fn ids(g: &Graph) -> BTreeMap<String, String> {
+        g.hashmap()
+            .values()
+            .map(|n| (n.segment.to_string(), n.id.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_graph_to_gfa() {
+        let g = yeild_graph();
+        let ids = ids(&g);
+        let (gatc, a, g_alt, rest) = (&ids["GATC"], &ids["A"], &ids["G"], &ids["TCGGTTACACAGCTA"]);
+
+        let mut out: Vec<u8> = Vec::new();
+        fmt_graph_to_gfa(&g, &mut out).unwrap();
+        let gfa = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = gfa.lines().collect();
+
+        assert_eq!(lines[0], "H\tVN:Z:1.0\ths:Z:sha256:text");
+        assert_eq!(lines.iter().filter(|l| l.starts_with('S')).count(), 4);
+        assert_eq!(lines.iter().filter(|l| l.starts_with('L')).count(), 4);
+
+        let hash = g.get_node(gatc.parse().unwrap()).unwrap().hash().unwrap();
+        assert!(lines
+            .contains(&format!("S\t{}\tGATC\tsh:Z:{}\tSN:Z:chr1\tSO:i:0", gatc, hash).as_str()));
+
+        // The alternative allele isn't on the reference
+        let hash = g.get_node(g_alt.parse().unwrap()).unwrap().hash().unwrap();
+        assert!(lines.contains(&format!("S\t{}\tG\tsh:Z:{}", g_alt, hash).as_str()));
+        assert!(lines.contains(&format!("L\t{}\t+\t{}\t+\t0M", gatc, g_alt).as_str()));
+        assert!(lines.contains(&format!("L\t{}\t+\t{}\t+\t0M", g_alt, rest).as_str()));
+
+        // The alternative allele isn't on the reference path
+        assert_eq!(
+            lines.last().unwrap(),
+            &format!("P\tchr1\t{}+,{}+,{}+\t*", gatc, a, rest)
+        );
+    }
+
+    #[test]
+    fn test_gfa_round_trip() {
+        let g = yeild_graph();
+
+        let mut out: Vec<u8> = Vec::new();
+        fmt_graph_to_gfa(&g, &mut out).unwrap();
+        let gfa = parse_gfa(Cursor::new(out)).unwrap();
+
+        assert_eq!(gfa.graph.hashmap().len(), g.hashmap().len());
+
+        for n in g.hashmap().values() {
+            let m = gfa.graph.get_node(n.id).unwrap();
+
+            assert_eq!(m.segment, n.segment);
+            assert_eq!(m.hash(), n.hash());
+            assert_eq!(m.reference(), n.reference());
+            assert_eq!(m.nodes_right, n.nodes_right);
+
+            // Only nodes on a path know where they are
+            if n.reference().is_some() {
+                assert_eq!(m.offset(), n.offset());
+            }
+        }
+
+        assert_eq!(gfa.graph.paths().count(), 1);
+        assert_eq!(gfa.graph.path("chr1"), g.path("chr1"));
+        assert_eq!(gfa.graph.scheme(), g.scheme());
+    }
+
+    #[test]
+    fn test_gfa_round_trip_then_update() {
+        // The paths of the haplotypes of A sort before chr1 so they're written first
+        let fasta = Cursor::new(">chr1\nGATCATCGGTTACACAGCTA\n");
+        let vcf = Cursor::new(
+            "\
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO	FORMAT	A
+chr1	5	rs21549	A	G	.	PASS	.	GT	1|1
+",
+        );
+        let options = ConstructOptions {
+            haplotypes: Some(Unphased::Skip),
+            ..ConstructOptions::default()
+        };
+        let (g, _) = reference::construct_with(fasta, vcf, &options).unwrap();
+
+        let mut out: Vec<u8> = Vec::new();
+        fmt_graph_to_gfa(&g, &mut out).unwrap();
+        let tagged = String::from_utf8(out).unwrap();
+
+        // Without the tags the nodes are placed by the path of chr1 rather than the haplotypes
+        let untagged: String = tagged
+            .lines()
+            .map(|line| {
+                let fields: Vec<&str> = line
+                    .split('\t')
+                    .filter(|f| !f.starts_with("SN:") && !f.starts_with("SO:"))
+                    .collect();
+                fields.join("\t") + "\n"
+            })
+            .collect();
+
+        for data in [tagged, untagged].iter() {
+            let mut graph = parse_gfa(Cursor::new(data.clone())).unwrap().graph;
+            let vcf = Cursor::new(
+                "\
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+chr1	10	rs21550	T	C	.	PASS	.
+",
+            );
+            let mut vcf_reader = VCFReader::new(vcf).unwrap();
+            update::update(&mut graph, &mut vcf_reader, RefCheck::Error).unwrap();
+
+            // TCGGTTACACAGCTA is split into TCGG, T|C and TACACAGCTA
+            assert_eq!(graph.hashmap().len(), 7);
+            assert_eq!(graph.path_sequence("chr1").unwrap(), "GATCATCGGTTACACAGCTA");
+            assert_eq!(
+                graph.path_sequence("A#1#chr1").unwrap(),
+                "GATCGTCGGTTACACAGCTA"
+            );
+        }
+    }
+
+    #[test]
+    fn test_haplotypes_to_gfa_walks() {
+        // The haplotypes of A are split around the unphased site so their second walks start at 5
+        let fasta = Cursor::new(">chr1\nGATCATCGGTTACACAGCTA\n");
+        let vcf = Cursor::new(
+            "\
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO	FORMAT	A
+chr1	5	rs21549	A	G	.	PASS	.	GT	1/0
+",
+        );
+        let options = ConstructOptions {
+            haplotypes: Some(Unphased::Split),
+            ..ConstructOptions::default()
+        };
+        let (g, _) = reference::construct_with(fasta, vcf, &options).unwrap();
+        let ids = ids(&g);
+        let (gatc, rest) = (&ids["GATC"], &ids["TCGGTTACACAGCTA"]);
+
+        let mut out: Vec<u8> = Vec::new();
+        fmt_graph_to_gfa(&g, &mut out).unwrap();
+        let gfa = String::from_utf8(out).unwrap();
+        let walks: Vec<&str> = gfa.lines().filter(|l| l.starts_with('W')).collect();
+
+        assert_eq!(
+            walks,
+            vec![
+                format!("W\tA\t1\tchr1\t0\t4\t>{}", gatc),
+                format!("W\tA\t1\tchr1\t5\t20\t>{}", rest),
+                format!("W\tA\t2\tchr1\t0\t4\t>{}", gatc),
+                format!("W\tA\t2\tchr1\t5\t20\t>{}", rest),
+            ]
+        );
+        assert_eq!(gfa.lines().filter(|l| l.starts_with('P')).count(), 1);
+
+        // They're read back as the same paths
+        let parsed = parse_gfa(Cursor::new(gfa.clone())).unwrap().graph;
+        let names =
+            |g: &Graph| -> Vec<String> { g.paths().map(|(name, _)| name.to_owned()).collect() };
+        assert_eq!(names(&parsed), names(&g));
+        for (name, path) in g.paths() {
+            assert_eq!(parsed.path(name), Some(path));
+        }
+    }
+
+    #[test]
+    fn test_parse_gfa() {
+        let data = "\
+H\tVN:Z:1.0
+S\ts1\tGATC\tLN:i:4\tRC:i:12
+S\ts2\tA
+S\ts3\tG
+S\ts4\tTCGG
+L\ts1\t+\ts2\t+\t0M
+L\ts3\t-\ts1\t-\t0M
+L\ts2\t+\ts4\t+\t*
+L\ts3\t+\ts4\t+\t0M
+L\ts4\t+\ts2\t-\t0M
+P\tchr1\ts1+,s2+,s4+\t*
+W\tHG002\t1\tchr1\t0\t9\t>s1>s3>s4
+W\tHG002\t2\tchr1\t0\t4\t<s4
+";
+        let gfa = parse_gfa(Cursor::new(data)).unwrap();
+        let g = &gfa.graph;
+        let id = |name: &str| gfa.segments[name];
+
+        assert_eq!(g.hashmap().len(), 4);
+        assert!(g.edge_exists(id("s1"), id("s2")).unwrap());
+        assert!(g.edge_exists(id("s1"), id("s3")).unwrap());
+        assert!(g.edge_exists(id("s2"), id("s4")).unwrap());
+        assert!(g.edge_exists(id("s3"), id("s4")).unwrap());
+
+        // An inversion can be read from either strand
+        let (s2, s4) = (Handle::forward(id("s2")), Handle::forward(id("s4")));
+        assert!(g.has_edge(s4, s2.flip()).unwrap());
+        assert!(g.has_edge(s2, s4.flip()).unwrap());
+        assert!(!g.edge_exists(id("s4"), id("s2")).unwrap());
+
+        // s3 is only on the W line
+        let s3 = g.get_node(id("s3")).unwrap();
+        assert_eq!(s3.reference(), Some("HG002#1#chr1"));
+        assert_eq!(s3.offset(), 4);
+
+        let s4 = g.get_node(id("s4")).unwrap();
+        assert_eq!(s4.reference(), Some("chr1"));
+        assert_eq!(s4.offset(), 5);
+
+        assert_eq!(
+            gfa.tags[&id("s1")],
+            vec![
+                Tag {
+                    name: String::from("LN"),
+                    kind: 'i',
+                    value: String::from("4")
+                },
+                Tag {
+                    name: String::from("RC"),
+                    kind: 'i',
+                    value: String::from("12")
+                },
+            ]
+        );
+
+        let forward = |name: &str| Handle::forward(id(name));
+        assert_eq!(
+            g.path("chr1").unwrap(),
+            &[forward("s1"), forward("s2"), forward("s4")]
+        );
+        assert_eq!(
+            g.path("HG002#1#chr1").unwrap(),
+            &[forward("s1"), forward("s3"), forward("s4")]
+        );
+        assert_eq!(g.path("HG002#2#chr1").unwrap(), &[forward("s4").flip()]);
+        assert_eq!(g.path_sequence("HG002#2#chr1").unwrap(), "CCGA");
+    }
+
+    #[test]
+    fn test_parse_gfa_ids() {
+        let hash = NodeHash::new("GATC", 0);
+        let data = format!(
+            "S\ts1\tGATC\nS\t7\tA\tsh:Z:{}\tLN:i:1\nS\t3\tG\nS\t{}\tT\n",
+            hash, hash
+        );
+        let gfa = parse_gfa(Cursor::new(data)).unwrap();
+        let node = |name: &str| gfa.graph.get_node(gfa.segments[name]).unwrap();
+
+        // Named segments get ids after the largest numeric one
+        assert_eq!(gfa.segments["7"], NodeId::new(7));
+        assert_eq!(gfa.segments["3"], NodeId::new(3));
+        assert_eq!(gfa.segments["s1"], NodeId::new(8));
+        assert_eq!(gfa.segments[&hash.to_string()], NodeId::new(9));
+
+        // The hash is read from the tag or the name and isn't kept as a tag
+        assert_eq!(node("7").hash(), Some(hash));
+        assert_eq!(node(&hash.to_string()).hash(), Some(hash));
+        assert_eq!(node("3").hash(), None);
+        assert_eq!(gfa.tags[&NodeId::new(7)].len(), 1);
+    }
+
+    #[test]
+    fn test_parse_gfa_errors() {
+        let parse = |data: &str| parse_gfa(Cursor::new(data.to_owned()));
+
+        // A link to a segment that isn't there
+        match parse("S\ts1\tGATC\nL\ts1\t+\ts2\t+\t0M\n") {
+            Err(Error::Malformed(msg)) => assert_eq!(msg, "line 2: unknown segment s2"),
+            _ => panic!("Expected a malformed input error"),
+        }
+
+        // A segment without a sequence field
+        match parse("S\ts1\n") {
+            Err(Error::Malformed(_)) => (),
+            _ => panic!("Expected a malformed input error"),
+        }
+
+        // A bad tag
+        match parse("S\ts1\tGATC\tLN\n") {
+            Err(Error::Malformed(_)) => (),
+            _ => panic!("Expected a malformed input error"),
+        }
+
+        // An overlap
+        match parse("S\ts1\tGATC\nS\ts2\tA\nL\ts1\t+\ts2\t+\t2M\n") {
+            Err(Error::Unsupported(_)) => (),
+            _ => panic!("Expected an unsupported input error"),
+        }
+
+        // Two paths with the same name
+        match parse("S\ts1\tGATC\nP\tchr1\ts1+\t*\nP\tchr1\ts1-\t*\n") {
+            Err(Error::Malformed(msg)) => {
+                assert_eq!(msg, "line 3: Path chr1 is already in the graph")
+            }
+            _ => panic!("Expected a malformed input error"),
+        }
+
+        // A header with a hash scheme we don't know
+        match parse("H\tVN:Z:1.0\ths:Z:md5\n") {
+            Err(Error::Malformed(msg)) => assert_eq!(msg, "line 1: bad hash scheme md5"),
+            _ => panic!("Expected a malformed input error"),
+        }
+
+        // A bad orientation
+        match parse("S\ts1\tGATC\nS\ts2\tA\nL\ts1\t+\ts2\t?\t0M\n") {
+            Err(Error::Malformed(_)) => (),
+            _ => panic!("Expected a malformed input error"),
+        }
+    }
+}
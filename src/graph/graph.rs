@@ -1,23 +1,50 @@
 // Reference
 use needletail::{self, SequenceRecord};
-use std::borrow::Cow;
-
-// Files
-use std::io::BufReader;
-use std::io::Read;
 
 // VCF
-use vcf::{self, VCFReader, VCFRecord};
+use vcf::{self, VCFRecord};
 
+use std::collections::HashMap;
 use std::str;
 
-use crate::graph::types::{self, Node};
-use crate::io::types::{Buf, Seeker};
+use crate::error::Error;
+use crate::graph::haplotypes::{self, Part};
+use crate::graph::types::{Graph, Handle, HashScheme, Node, NodeId};
+use crate::graph::update::{self, ReferenceIndex};
+use crate::io::filter::{RecordFilter, Region};
+use crate::io::types::{Seeker, Window};
+use crate::io::variation::{self, Alleles, RefCheck, StructuralVariant, Unphased};
+
+/// How to build a graph out of a reference and variation data
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ConstructOptions {
+    // How the nodes are hashed
+    pub scheme: HashScheme,
+
+    // What to do with records that don't match the reference
+    pub ref_check: RefCheck,
+
+    // Whether to add a path for each haplotype of each sample
+    // and what to do with genotypes that don't say which allele a haplotype has
+    pub haplotypes: Option<Unphased>,
+
+    // The samples whose haplotypes get paths, all of them when None
+    pub samples: Option<Vec<String>>,
+
+    // Which records to build into the graph
+    pub filter: RecordFilter,
+
+    // The parts of the reference to build the graph of, all of it when empty
+    pub regions: Vec<Region>,
+}
+
 /*
-Split the reference based on variation data.
+Split the reference based on variation data and build the variation graph.
 
-Read the records (values) in `vcf_reader` and split the
-reference based on the chromosome/id & the positions.
+Read the records (values) in `vcf_records` and split the
+reference based on the positions.
+The records are the ones whose chromosome/id is that of `seq_record`
+ordered by position. See `variation::group_variations`.
 
 A VCF record is a data lines that contains marker and genotype data (one variant per line).
 
@@ -29,77 +56,83 @@ For our purposes, the following will be (somewhat) synonymous:
 *Loop* through the variation data and split there reference file/data
 based on the variations.
 
-BUG:
-Skips chromosomes when the order of chromosomes on the reference
-isn't the same as the order of chromosomes in the VCF.
+Each record yields:
+ - a conserved node spanning the reference between the previous record and this one
+ - a node for the reference allele
+ - a node for each alternative allele
+
+The reference and alternative allele nodes form a bubble between the
+conserved nodes on either side of the record.
+Indels leave out the base VCF anchors them with (see `variation::Alleles`)
+so one of their alleles is empty, it's an edge that goes around the others.
+
+The conserved and reference allele nodes in order make up a path named after the sequence.
+
+Records are checked against the reference first, see `ConstructOptions`.
+Those that don't match and that we don't stop at go in `warnings`
+along with the haplotypes that are left out.
+Records that overlap, such as a deletion over a SNP, make a single bubble
+with the alleles of all of them (see `variation::sites`).
+
+Symbolic `<DEL>` and `<INS>` alleles are alleles like any other.
+`<INV>` and `<DUP>` are edges between the reference nodes they span once
+the rest of the graph is built, see `update::add_rearrangement`.
+Breakends can join any two sequences so construction leaves them
+until all of the sequences are in the graph, see `update::join_breakends`.
+
+With `options.haplotypes` the haplotypes of `samples`, named as in the VCF,
+are paths through the graph too,
+see `haplotypes::thread_haplotypes`.
  */
-pub fn splitter<R: Read>(
-    seq_record: SequenceRecord,
-    vcf_reader: &mut VCFReader<BufReader<R>>,
-    vcf_record_buffer: &mut Buf<VCFRecord>,
-    opt_seeker: &mut Option<Seeker>,
-) -> () {
+pub fn splitter(
+    seq_record: &SequenceRecord,
+    vcf_records: &[VCFRecord],
+    samples: &[&str],
+    options: &ConstructOptions,
+    warnings: &mut Vec<Error>,
+) -> Result<Graph, Error> {
     /*
     Name and/or a unique identifier for the sequence
     Most times refers to a chromosome
      */
-    let sequence_id = str::from_utf8(&seq_record.id).unwrap();
-    println!("Processing sequence with ID {}", sequence_id);
-
-    //
-    let mut process_record = |record: &VCFRecord, seq_record: &SequenceRecord| {
-        let seq: &Cow<[u8]> = &seq_record.seq;
-        let reference = str::from_utf8(&seq_record.id).unwrap();
-        let record_pos = record.position as usize; // end
-
-        let start = match opt_seeker {
-            // If there's a seeker and it matches the chromosome use it.
-            Some(seeker) => {
-                if seeker.chromosome() == record.chromosome {
-                    seeker.position() as usize
-                } else {
-                    // this is the first split for this sequence so again start at 0
-                    0
-                }
-            }
-            // If there isn't a seeker it means this is the first split so we start at 0
-            _ => 0,
-        };
-
-        // Slice the sequence
-        let p = &seq[start..record_pos];
-        let p = str::from_utf8(p).unwrap();
-        let n = Node::new(p, record_pos, reference, Vec::new(), Vec::new());
-        println!("Node: {}", n);
+    let sequence_id = sequence_name(seq_record)?;
+    let seq = Window::whole(&seq_record.seq);
+    splitter_within(sequence_id, seq, vcf_records, samples, options, warnings)
+}
 
-        // Update the seeker
-        let new_seeker = Seeker::new(record.chromosome.clone(), record.position);
-        opt_seeker.replace(new_seeker);
+/// Build the graph of the part of a sequence in `seq` like `splitter` does the whole of it.
+///
+/// Records that aren't all in `seq` are left out, other than those past the end
+/// of the sequence when `seq` runs to it since they are out of range.
+/// The path of the reference is named `sequence[start-end]` after the part
+/// unless it's the whole sequence.
+pub fn splitter_within(
+    sequence_id: &str,
+    seq: Window,
+    vcf_records: &[VCFRecord],
+    samples: &[&str],
+    options: &ConstructOptions,
+    warnings: &mut Vec<Error>,
+) -> Result<Graph, Error> {
+    let path = if seq.is_whole() {
+        sequence_id.to_owned()
+    } else {
+        format!("{}[{}-{}]", sequence_id, seq.start(), seq.end())
     };
 
-    /*
-    Don't iterate before checking whether there's a record in the `vcf_record_buffer`
-    because the iterator may skip the current value in the buffer and lose some records
-    This is flex's way of moving the cursor one record back to the previous variation value.
-     */
-    if vcf_record_buffer.has_value() {
-        let buffered_record = vcf_record_buffer.read().unwrap();
-
-        /*
-        If the chromosome value of the VCF record in the buffer doesn't match the `sequence_id`
-        Put it back in the buffer else process the record.
-         */
-        if buffered_record.chromosome == sequence_id {
-            process_record(&buffered_record, &seq_record);
-        } else {
-            vcf_record_buffer.write(buffered_record);
-            return;
-        }
-    }
+    // The graph owns its sequences so that it can outlive the reference
+    let mut graph = Graph::with_scheme(options.scheme);
+    graph.add_path(&path)?;
+
+    // The nodes whose right side is still open.
+    // The next node we add to the graph gets an edge from each of them.
+    let mut tails: Vec<NodeId> = Vec::new();
+
+    // Start seeker at the start of the part
+    let mut seeker = Seeker::new(sequence_id.to_owned(), seq.start() as u64);
 
     /*
-    Loop through the variation data and take a split the reference if the
-    chromosome matches the value of the sequence.
+    Loop through the variation data and split the reference.
 
     Splitting the reference is based on acquiring slices of the reference/sequence.
 
@@ -111,21 +144,446 @@ pub fn splitter<R: Read>(
     The locus that contains the variation is then going to make an alternative node
     to the reference node.
      */
-    for opt_record in vcf_reader.iter() {
-        let vcf_record = opt_record.unwrap();
+    let mut alleles: Vec<Alleles> = Vec::new();
+    let mut rearrangements: Vec<StructuralVariant> = Vec::new();
+
+    // The records with a site and where the site is for the paths of the haplotypes
+    let mut threaded: Vec<(&VCFRecord, usize)> = Vec::new();
+
+    for record in vcf_records {
+        // VCF positions are 1 based and point at the first base of the reference allele
+        let record_start = (record.position as usize).saturating_sub(1);
+        let record_end = record_start + record.reference.len();
+
+        let variants = StructuralVariant::new(record)?;
+
+        // Records past the end of the part are out of range when it's the end of the sequence
+        let end = variants
+            .iter()
+            .map(|variant| variant.span().1)
+            .fold(record_end, usize::max);
+        if record_start < seq.start() || (end > seq.end() && seq.end() < seq.length()) {
+            continue;
+        }
+
+        // The reference each structural variant spans, None if it isn't all on the sequence
+        let spans: Vec<Option<&[u8]>> = variants
+            .iter()
+            .map(|variant| {
+                let (start, end) = variant.span();
+                seq.get(start..end)
+            })
+            .collect();
 
-        if vcf_record.chromosome == sequence_id {
-            process_record(&vcf_record, &seq_record)
+        // A structural variant that isn't on the sequence makes the record out of range
+        let found = if spans.iter().all(Option::is_some) {
+            seq.get(record_start..record_end)
         } else {
-            // store the VCF record in a buffer
-            // use it when we start to read that part of the reference
-            println!(
-                "Moving to another sequence because of chromosome {} id {}",
-                vcf_record.chromosome, sequence_id
+            None
+        };
+
+        let checked = variation::check_reference(record, found);
+        if !options.ref_check.keep(checked, warnings)? {
+            continue;
+        }
+
+        // A record whose alleles are all structural variants has no site of its own
+        let record_alleles = Alleles::new(record);
+        let first = alleles.len();
+        if variants.is_empty() || !record_alleles.alternatives.is_empty() {
+            alleles.push(record_alleles);
+        }
+
+        for (variant, reference) in variants.into_iter().zip(spans) {
+            match variant.alleles(str::from_utf8(reference.unwrap_or_default())?) {
+                Some(variant_alleles) => alleles.push(variant_alleles),
+                None => rearrangements.push(variant),
+            }
+        }
+
+        if let Some(first) = alleles.get(first) {
+            threaded.push((record, first.start));
+        }
+    }
+
+    // The conserved nodes and sites in order
+    let mut parts: Vec<Part> = Vec::new();
+
+    for alleles in variation::sites(alleles, &seq) {
+        let start = seeker.position() as usize;
+
+        // Slice the sequence up to the variation into a conserved node
+        if start < alleles.start {
+            let p = str::from_utf8(&seq[start..alleles.start])?;
+            let n = Node::new(
+                p.to_owned(),
+                start,
+                sequence_id.to_owned(),
+                Vec::new(),
+                Vec::new(),
+            );
+            tails = vec![add_linked_node(&mut graph, &tails, n)?];
+            graph.append_step(&path, Handle::forward(tails[0]))?;
+            parts.push(Part::Conserved(tails[0]));
+        }
+
+        // The reference allele and each alternative allele make a bubble
+        let mut next_tails: Vec<NodeId> = Vec::new();
+        let mut nodes: HashMap<String, Option<NodeId>> = HashMap::new();
+
+        if !alleles.reference.is_empty() {
+            let p = str::from_utf8(&seq[alleles.start..alleles.end()])?;
+            let n = Node::new(
+                p.to_owned(),
+                alleles.start,
+                sequence_id.to_owned(),
+                Vec::new(),
+                Vec::new(),
             );
-            println!("Storing record with ID {} \n\n", vcf_record.chromosome);
-            vcf_record_buffer.write(vcf_record);
-            break;
+            let id = add_linked_node(&mut graph, &tails, n)?;
+            graph.append_step(&path, Handle::forward(id))?;
+            next_tails.push(id);
+            nodes.insert(alleles.reference.clone(), Some(id));
+        }
+
+        for alt in alleles.alternatives.iter().filter(|alt| !alt.is_empty()) {
+            let n = Node::alternative(alt.clone(), alleles.start, Vec::new(), Vec::new());
+            let id = add_linked_node(&mut graph, &tails, n)?;
+            next_tails.push(id);
+            nodes.insert(alt.clone(), Some(id));
+        }
+
+        // An empty allele is an edge from the nodes before the record to those after it
+        if alleles.has_empty() {
+            next_tails.extend(tails);
+            nodes.insert(String::new(), None);
+        }
+
+        next_tails.sort();
+        next_tails.dedup();
+        tails = next_tails;
+
+        // Update the seeker
+        seeker = Seeker::new(sequence_id.to_owned(), alleles.end() as u64);
+        parts.push(Part::Site(alleles, nodes));
+    }
+
+    // Whatever is left of the sequence after the last variation is conserved
+    let start = seeker.position() as usize;
+
+    if start < seq.end() {
+        let p = str::from_utf8(&seq[start..])?;
+        let n = Node::new(
+            p.to_owned(),
+            start,
+            sequence_id.to_owned(),
+            Vec::new(),
+            Vec::new(),
+        );
+        let id = add_linked_node(&mut graph, &tails, n)?;
+        graph.append_step(&path, Handle::forward(id))?;
+        parts.push(Part::Conserved(id));
+    }
+
+    // Splitting nodes for the rearrangements keeps the paths up to date but not `parts`
+    if let Some(unphased) = options.haplotypes {
+        warnings.extend(haplotypes::thread_haplotypes(
+            &mut graph,
+            sequence_id,
+            seq.start(),
+            &parts,
+            &threaded,
+            samples,
+            unphased,
+        )?);
+    }
+
+    let mut index = ReferenceIndex::new(&graph);
+    for variant in rearrangements.iter() {
+        update::add_rearrangement(&mut graph, &mut index, sequence_id, variant)?;
+    }
+
+    Ok(graph)
+}
+
+/// The name of a sequence is the first word of its FASTA header
+pub fn sequence_name<'a>(seq_record: &'a SequenceRecord) -> Result<&'a str, Error> {
+    let id = str::from_utf8(&seq_record.id)?;
+    Ok(id.split_whitespace().next().unwrap_or(id))
+}
+
+// Add a node to the graph with an edge from each of the `tails` to it
+fn add_linked_node(graph: &mut Graph, tails: &[NodeId], n: Node) -> Result<NodeId, Error> {
+    let id = graph.add_node(n);
+
+    for tail in tails {
+        graph.add_edge_from_id(*tail, id)?;
+    }
+
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::variation;
+    use std::collections::HashMap;
+    use std::io::Cursor;
+    use vcf::VCFReader;
+
+    fn yeild_vcf() -> Cursor<&'static str> {
+        Cursor::new(
+            "
+##fileformat=VCFv4.2
+##FORMAT=<ID=GT,Number=1,Type=Integer,Description=\"Genotype\">
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO	FORMAT	SAMP001
+chr1	5	rs21549	A	G	.	PASS	.	GT	0/1
+chr1	10	rs21550	T	C,TTG	.	PASS	.	GT	0/1
+",
+        )
+    }
+
+    // The graph of chr1 in `yeild_fasta` with the variation in `records`
+    fn yeild_graph(records: &str) -> Graph {
+        yeild_graph_with(records, &ConstructOptions::default())
+    }
+
+    fn yeild_graph_with(records: &str, options: &ConstructOptions) -> Graph {
+        let vcf = format!(
+            "##fileformat=VCFv4.2\n#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n{}",
+            records
+        );
+        let mut vcf_reader = VCFReader::new(Cursor::new(vcf)).unwrap();
+        let records = variation::gen_variations(&mut vcf_reader).unwrap();
+
+        let mut graph = None;
+        needletail::parse_sequence_reader(
+            Cursor::new(">chr1\nGATCATCGGTTACACAGCTA\n"),
+            |_| {},
+            |seq| graph = Some(splitter(&seq, &records, &[], options, &mut Vec::new()).unwrap()),
+        )
+        .unwrap();
+
+        graph.unwrap()
+    }
+
+    // The ids of the nodes in a graph keyed by their segments
+    fn ids(graph: &Graph) -> HashMap<String, NodeId> {
+        graph
+            .hashmap()
+            .values()
+            .map(|n| (n.segment.clone(), n.id))
+            .collect()
+    }
+
+    fn yeild_fasta() -> Cursor<&'static str> {
+        Cursor::new(
+            "\
+>chr1 first sequence
+GATCATCGGTTACACAGCTA
+>chr2
+TCTTGTTCTC
+",
+        )
+    }
+
+    #[test]
+    fn test_splitter_emits_allele_nodes_and_edges() {
+        let mut vcf_reader = VCFReader::new(yeild_vcf()).unwrap();
+        let mut variations = variation::group_variations(&mut vcf_reader).unwrap();
+
+        // (sequence, node count, edge count)
+        let mut counts: Vec<(String, usize, usize)> = Vec::new();
+
+        needletail::parse_sequence_reader(
+            yeild_fasta(),
+            |_| {},
+            |seq| {
+                let id = sequence_name(&seq).unwrap().to_owned();
+                let records = variations.remove(&id).unwrap_or_default();
+                let graph = splitter(
+                    &seq,
+                    &records,
+                    &[],
+                    &ConstructOptions::default(),
+                    &mut Vec::new(),
+                )
+                .unwrap();
+
+                let hm = graph.hashmap();
+                let edges = hm.values().map(|n| n.nodes_right.len()).sum();
+                counts.push((id, hm.len(), edges));
+
+                // The reference is a path through the graph
+                let reference = String::from_utf8(seq.seq.to_vec()).unwrap();
+                assert_eq!(
+                    graph.path_sequence(sequence_name(&seq).unwrap()).unwrap(),
+                    reference
+                );
+            },
+        )
+        .unwrap();
+
+        // chr1: GATC, A|G, TCGG, T|C|TTG, TACACAGCTA
+        // chr2: a single conserved node
+        assert_eq!(
+            counts,
+            vec![(String::from("chr1"), 8, 10), (String::from("chr2"), 1, 0)]
+        );
+    }
+
+    #[test]
+    fn test_splitter_handles_indels() {
+        // GATC AT|. CGGT .|GG TA C ACAGC T|G A
+        let graph = yeild_graph(
+            "\
+chr1	4	del	CAT	C	.	PASS	.
+chr1	10	ins	T	TGG	.	PASS	.
+chr1	13	none	C	.	.	PASS	.
+chr1	19	snv	T	G,G	.	PASS	.
+",
+        );
+        let ids = ids(&graph);
+        let edge = |x: &str, y: &str| graph.edge_exists(ids[x], ids[y]).unwrap();
+
+        let mut segments: Vec<&str> = ids.keys().map(|segment| segment.as_str()).collect();
+        segments.sort();
+        assert_eq!(
+            segments,
+            vec!["A", "ACAGC", "AT", "C", "CGGT", "G", "GATC", "GG", "T", "TA"]
+        );
+
+        // The deletion goes around AT and the reference goes around the insertion
+        assert!(edge("GATC", "AT") && edge("AT", "CGGT") && edge("GATC", "CGGT"));
+        assert!(edge("CGGT", "GG") && edge("GG", "TA") && edge("CGGT", "TA"));
+        assert!(edge("TA", "C") && edge("C", "ACAGC"));
+        assert!(edge("ACAGC", "T") && edge("ACAGC", "G") && edge("G", "A"));
+        assert_eq!(graph.edges().len(), 12);
+
+        assert_eq!(graph.path_sequence("chr1").unwrap(), "GATCATCGGTTACACAGCTA");
+    }
+
+    #[test]
+    fn test_splitter_keeps_repeated_alleles_apart() {
+        // GATC A|G TCGGTT A|G CACAGCTA, the same alleles at two positions
+        let records = "\
+chr1	5	first	A	G	.	PASS	.
+chr1	12	second	A	G	.	PASS	.
+";
+
+        for scheme in &["sha256:text", "sha256:binary", "sha256:none"] {
+            let options = ConstructOptions {
+                scheme: scheme.parse().unwrap(),
+                ..ConstructOptions::default()
+            };
+            let graph = yeild_graph_with(records, &options);
+
+            assert_eq!(graph.hashmap().len(), 7, "{}", scheme);
+            assert_eq!(graph.edges().len(), 8, "{}", scheme);
+            assert_eq!(graph.path_sequence("chr1").unwrap(), "GATCATCGGTTACACAGCTA");
+        }
+    }
+
+    #[test]
+    fn test_splitter_keeps_insertions_apart_from_conserved_nodes() {
+        // GATCATCGGT .|TA TA C|G ACAGCTA, the inserted TA spells the conserved node after it
+        let graph = yeild_graph(
+            "\
+chr1	10	ins	T	TTA	.	PASS	.
+chr1	13	snv	C	G	.	PASS	.
+",
+        );
+
+        assert_eq!(graph.hashmap().len(), 6);
+        assert_eq!(graph.edges().len(), 7);
+        assert!(graph
+            .hashmap()
+            .values()
+            .all(|n| !graph.edge_exists(n.id, n.id).unwrap()));
+        assert_eq!(graph.path_sequence("chr1").unwrap(), "GATCATCGGTTACACAGCTA");
+    }
+
+    #[test]
+    fn test_splitter_merges_overlapping_records() {
+        // GATC ATCG|.|GTCG|ATAC GTTACACAG CT|ATCT|. A
+        let graph = yeild_graph(
+            "\
+chr1	4	del	CATCG	C	.	PASS	.
+chr1	5	snv	A	G	.	PASS	.
+chr1	7	mnv	CG	AC	.	PASS	.
+chr1	17	ins	G	GAT	.	PASS	.
+chr1	17	del2	GCT	G	.	PASS	.
+",
+        );
+        let ids = ids(&graph);
+        let edge = |x: &str, y: &str| graph.edge_exists(ids[x], ids[y]).unwrap();
+
+        let mut segments: Vec<&str> = ids.keys().map(|segment| segment.as_str()).collect();
+        segments.sort();
+        assert_eq!(
+            segments,
+            vec![
+                "A",
+                "ATAC",
+                "ATCG",
+                "ATCT",
+                "CT",
+                "GATC",
+                "GTCG",
+                "GTTACACAG"
+            ]
+        );
+
+        // Each group of overlapping records is a single bubble
+        for allele in &["ATCG", "GTCG", "ATAC"] {
+            assert!(edge("GATC", allele) && edge(allele, "GTTACACAG"));
         }
+        assert!(edge("GATC", "GTTACACAG"));
+        for allele in &["CT", "ATCT"] {
+            assert!(edge("GTTACACAG", allele) && edge(allele, "A"));
+        }
+        assert!(edge("GTTACACAG", "A"));
+        assert_eq!(graph.edges().len(), 12);
+
+        assert_eq!(graph.path_sequence("chr1").unwrap(), "GATCATCGGTTACACAGCTA");
+    }
+
+    #[test]
+    fn test_splitter_handles_structural_variants() {
+        // GATC ATCG|. GT .|GG T ACA|-ACA CA G C|G C TA
+        // A record with a symbolic allele we leave out is like one without alternatives
+        let graph = yeild_graph(
+            "\
+chr1	4	del	C	<DEL>	.	PASS	END=8
+chr1	10	ins	T	<INS>	.	PASS	SVINSSEQ=GG
+chr1	11	inv	T	<INV>	.	PASS	END=14
+chr1	16	dup	A	<DUP:TANDEM>	.	PASS	SVLEN=2
+chr1	17	cnv	G	<CNV>	.	PASS	END=18
+",
+        );
+        let ids = ids(&graph);
+        let edge = |x: Handle, y: Handle| graph.has_edge(x, y).unwrap();
+        let forward = |segment: &str| Handle::forward(ids[segment]);
+        let reverse = |segment: &str| Handle::reverse(ids[segment]);
+
+        let mut segments: Vec<&str> = ids.keys().map(|segment| segment.as_str()).collect();
+        segments.sort();
+        assert_eq!(
+            segments,
+            vec!["ACA", "ATCG", "C", "CA", "G", "GATC", "GG", "GT", "T", "TA"]
+        );
+
+        // The deletion and the insertion are bubbles
+        assert!(edge(forward("GATC"), forward("GT")) && edge(forward("ATCG"), forward("GT")));
+        assert!(edge(forward("GT"), forward("GG")) && edge(forward("GG"), forward("T")));
+
+        // The inversion goes through the reverse strand of the nodes it spans
+        assert!(edge(forward("T"), reverse("ACA")) && edge(reverse("ACA"), forward("CA")));
+
+        // The duplication goes back to where it starts
+        assert!(edge(forward("C"), forward("G")));
+
+        assert_eq!(graph.edges().len(), 14);
+        assert_eq!(graph.path_sequence("chr1").unwrap(), "GATCATCGGTTACACAGCTA");
     }
 }
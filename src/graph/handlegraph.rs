@@ -0,0 +1,718 @@
+//! Traits for reading and changing a graph through handles as in [libhandlegraph]
+//!
+//! Algorithms written against these traits work with any graph that implements them.
+//!
+//! [libhandlegraph]: https://github.com/vgteam/libhandlegraph
+
+use crate::error::Error;
+use crate::graph::types::{Graph, Handle, Node, NodeId, Orientation};
+
+/// Read access to a bidirected graph
+///
+/// Handles passed to these methods are expected to be on nodes in the graph.
+/// Methods that read a node panic if it isn't.
+pub trait HandleGraph {
+    /// Whether a node with `id` is in the graph
+    fn has_node(&self, id: NodeId) -> bool;
+
+    /// The handle of the node with `id` on the given strand
+    fn get_handle(&self, id: NodeId, is_reverse: bool) -> Handle {
+        let orientation = if is_reverse {
+            Orientation::Reverse
+        } else {
+            Orientation::Forward
+        };
+
+        Handle::new(id, orientation)
+    }
+
+    fn get_id(&self, handle: Handle) -> NodeId {
+        handle.id()
+    }
+
+    fn get_is_reverse(&self, handle: Handle) -> bool {
+        handle.is_reverse()
+    }
+
+    /// The same node on the other strand
+    fn flip(&self, handle: Handle) -> Handle {
+        handle.flip()
+    }
+
+    /// The length of the sequence of the node of `handle`
+    fn get_length(&self, handle: Handle) -> usize;
+
+    /// The sequence of the node of `handle` read on its strand
+    fn get_sequence(&self, handle: Handle) -> String;
+
+    /// Call `iteratee` with each handle that is next to `handle` on its right,
+    /// or on its left when `go_left` is set.
+    /// Stops early and returns false once `iteratee` returns false.
+    fn follow_edges<F: FnMut(Handle) -> bool>(
+        &self,
+        handle: Handle,
+        go_left: bool,
+        iteratee: F,
+    ) -> bool;
+
+    /// Call `iteratee` with the forward handle of each node.
+    /// Stops early and returns false once `iteratee` returns false.
+    fn for_each_handle<F: FnMut(Handle) -> bool>(&self, iteratee: F) -> bool;
+
+    fn get_node_count(&self) -> usize;
+
+    /// The smallest node id or None when the graph is empty
+    fn min_node_id(&self) -> Option<NodeId>;
+
+    /// The largest node id or None when the graph is empty
+    fn max_node_id(&self) -> Option<NodeId>;
+
+    /// The number of edges on the right of `handle`, or on its left when `go_left` is set
+    fn get_degree(&self, handle: Handle, go_left: bool) -> usize {
+        let mut degree = 0;
+        self.follow_edges(handle, go_left, |_| {
+            degree += 1;
+            true
+        });
+
+        degree
+    }
+}
+
+/// A graph that nodes and edges can be added to
+pub trait MutableHandleGraph: HandleGraph {
+    /// Add a node with `sequence` that isn't on a reference
+    fn create_handle(&mut self, sequence: &str) -> Handle;
+
+    /// Add an edge from the end of `left` to the start of `right`.
+    /// Nothing changes if the edge is already there.
+    fn create_edge(&mut self, left: Handle, right: Handle) -> Result<(), Error>;
+
+    /// Make the strand of `handle` the forward strand of its node
+    /// and return the forward handle of the node, which may have a new id
+    fn apply_orientation(&mut self, handle: Handle) -> Result<Handle, Error>;
+
+    /// Split the node of `handle` at each of `offsets` along the strand of `handle`.
+    /// The offsets must be increasing and inside the node.
+    /// Returns the handles of the pieces in the order `handle` reads them.
+    fn divide_handle(&mut self, handle: Handle, offsets: &[usize]) -> Result<Vec<Handle>, Error>;
+}
+
+/// A graph that nodes and edges can be removed from
+pub trait DeletableHandleGraph: MutableHandleGraph {
+    /// Remove the node of `handle` along with its edges
+    fn destroy_handle(&mut self, handle: Handle) -> Result<(), Error>;
+
+    /// Remove the edge from the end of `left` to the start of `right` if there's one
+    fn destroy_edge(&mut self, left: Handle, right: Handle) -> Result<(), Error>;
+
+    /// Cut the node of `handle` at `offset` along the strand of `handle` and keep
+    /// the part on the right of `offset`, or on the left when `trunc_left` isn't set.
+    /// Edges on the side that was cut off are removed.
+    /// Returns the handle of the part that's kept.
+    fn truncate_handle(
+        &mut self,
+        handle: Handle,
+        trunc_left: bool,
+        offset: usize,
+    ) -> Result<Handle, Error>;
+}
+
+/// A graph with named paths, each an ordered list of steps on handles
+pub trait PathHandleGraph: HandleGraph {
+    fn get_path_count(&self) -> usize;
+
+    fn has_path(&self, name: &str) -> bool;
+
+    /// Call `iteratee` with the name of each path.
+    /// Stops early and returns false once `iteratee` returns false.
+    fn for_each_path<F: FnMut(&str) -> bool>(&self, iteratee: F) -> bool;
+
+    /// The number of steps in a path or None when there's no such path
+    fn get_step_count(&self, name: &str) -> Option<usize>;
+
+    /// Call `iteratee` with the handle of each step of a path in order.
+    /// Stops early and returns false once `iteratee` returns false.
+    fn for_each_step_in_path<F: FnMut(Handle) -> bool>(
+        &self,
+        name: &str,
+        iteratee: F,
+    ) -> Result<bool, Error>;
+
+    /// Call `iteratee` with the name of the path and the index of each step on the node of `handle`.
+    /// Stops early and returns false once `iteratee` returns false.
+    fn for_each_step_on_handle<F: FnMut(&str, usize) -> bool>(
+        &self,
+        handle: Handle,
+        iteratee: F,
+    ) -> bool;
+
+    /// The sequence spelled out by the steps of a path
+    fn get_path_sequence(&self, name: &str) -> Result<String, Error> {
+        let mut sequence = String::new();
+        self.for_each_step_in_path(name, |h| {
+            sequence.push_str(&self.get_sequence(h));
+            true
+        })?;
+
+        Ok(sequence)
+    }
+}
+
+/// A graph whose paths can be changed
+pub trait MutablePathHandleGraph: PathHandleGraph {
+    /// Add a path without any steps
+    fn create_path(&mut self, name: &str) -> Result<(), Error>;
+
+    /// Add a step on `handle` to the end of a path
+    fn append_step(&mut self, name: &str, handle: Handle) -> Result<(), Error>;
+
+    fn destroy_path(&mut self, name: &str) -> Result<(), Error>;
+}
+
+impl HandleGraph for Graph {
+    fn has_node(&self, id: NodeId) -> bool {
+        Graph::has_node(self, id)
+    }
+
+    fn get_length(&self, handle: Handle) -> usize {
+        self.get_node(handle.id()).unwrap().segment.len()
+    }
+
+    fn get_sequence(&self, handle: Handle) -> String {
+        self.sequence(handle).unwrap()
+    }
+
+    fn follow_edges<F: FnMut(Handle) -> bool>(
+        &self,
+        handle: Handle,
+        go_left: bool,
+        mut iteratee: F,
+    ) -> bool {
+        // Going left of a handle is going right of its flip and reading what we find flipped
+        let (h, flip) = if go_left {
+            (handle.flip(), true)
+        } else {
+            (handle, false)
+        };
+
+        let n = self.get_node(h.id()).unwrap();
+
+        // The right of a reverse handle is the left side of its node read on the other strand
+        let (side, flip) = if h.is_reverse() {
+            (&n.nodes_left, !flip)
+        } else {
+            (&n.nodes_right, flip)
+        };
+
+        side.iter()
+            .map(|next| if flip { next.flip() } else { *next })
+            .all(&mut iteratee)
+    }
+
+    fn for_each_handle<F: FnMut(Handle) -> bool>(&self, mut iteratee: F) -> bool {
+        self.hashmap()
+            .keys()
+            .all(|id| iteratee(Handle::forward(*id)))
+    }
+
+    fn get_node_count(&self) -> usize {
+        self.hashmap().len()
+    }
+
+    fn min_node_id(&self) -> Option<NodeId> {
+        self.hashmap().keys().min().cloned()
+    }
+
+    fn max_node_id(&self) -> Option<NodeId> {
+        self.hashmap().keys().max().cloned()
+    }
+}
+
+impl MutableHandleGraph for Graph {
+    // The node has no position so it gets a fresh id and no hash
+    fn create_handle(&mut self, sequence: &str) -> Handle {
+        let n = Node::with_hash(None, sequence.to_owned(), 0, None, Vec::new(), Vec::new());
+
+        Handle::forward(self.add_node(n))
+    }
+
+    fn create_edge(&mut self, left: Handle, right: Handle) -> Result<(), Error> {
+        if self.has_edge(left, right)? {
+            return Ok(());
+        }
+
+        self.add_edge(left, right)
+    }
+
+    fn apply_orientation(&mut self, handle: Handle) -> Result<Handle, Error> {
+        if !Graph::has_node(self, handle.id()) {
+            return Err(Error::MissingNode(handle.id()));
+        }
+
+        if !handle.is_reverse() {
+            return Ok(handle);
+        }
+
+        let id = self.reverse_node(handle.id())?;
+
+        Ok(Handle::forward(id))
+    }
+
+    fn divide_handle(&mut self, handle: Handle, offsets: &[usize]) -> Result<Vec<Handle>, Error> {
+        let (start, length) = match self.get_node(handle.id()) {
+            Some(n) => (n.offset(), n.segment.len()),
+            None => return Err(Error::MissingNode(handle.id())),
+        };
+
+        // Check the offsets before we change anything
+        let mut previous = 0;
+        for offset in offsets {
+            if *offset <= previous || *offset >= length {
+                return Err(Error::OutOfRange {
+                    contig: handle.to_string(),
+                    position: *offset as u64 + 1,
+                });
+            }
+
+            previous = *offset;
+        }
+
+        // Offsets along the reverse strand are read from the end of the forward strand
+        let forward_offsets: Vec<usize> = if handle.is_reverse() {
+            offsets.iter().rev().map(|offset| length - offset).collect()
+        } else {
+            offsets.to_vec()
+        };
+
+        let mut pieces: Vec<Handle> = Vec::new();
+        let mut rest = handle.id();
+
+        for offset in forward_offsets {
+            let (left, right) = self.split_node(rest, start + offset)?;
+            pieces.push(Handle::forward(left));
+            rest = right;
+        }
+        pieces.push(Handle::forward(rest));
+
+        if handle.is_reverse() {
+            pieces = pieces.iter().rev().map(|h| h.flip()).collect();
+        }
+
+        Ok(pieces)
+    }
+}
+
+impl DeletableHandleGraph for Graph {
+    fn destroy_handle(&mut self, handle: Handle) -> Result<(), Error> {
+        self.remove_node(handle.id())?;
+
+        Ok(())
+    }
+
+    fn destroy_edge(&mut self, left: Handle, right: Handle) -> Result<(), Error> {
+        self.remove_edge(left, right)
+    }
+
+    fn truncate_handle(
+        &mut self,
+        handle: Handle,
+        trunc_left: bool,
+        offset: usize,
+    ) -> Result<Handle, Error> {
+        let length = match self.get_node(handle.id()) {
+            Some(n) => n.segment.len(),
+            None => return Err(Error::MissingNode(handle.id())),
+        };
+
+        // Nothing to cut off
+        if (trunc_left && offset == 0) || (!trunc_left && offset == length) {
+            return Ok(handle);
+        }
+
+        let pieces = self.divide_handle(handle, &[offset])?;
+        let (cut, kept) = if trunc_left {
+            (pieces[0], pieces[1])
+        } else {
+            (pieces[1], pieces[0])
+        };
+
+        self.destroy_handle(cut)?;
+
+        Ok(kept)
+    }
+}
+
+impl PathHandleGraph for Graph {
+    fn get_path_count(&self) -> usize {
+        self.paths().count()
+    }
+
+    fn has_path(&self, name: &str) -> bool {
+        Graph::has_path(self, name)
+    }
+
+    fn for_each_path<F: FnMut(&str) -> bool>(&self, mut iteratee: F) -> bool {
+        self.paths().all(|(name, _)| iteratee(name))
+    }
+
+    fn get_step_count(&self, name: &str) -> Option<usize> {
+        self.path(name).map(|steps| steps.len())
+    }
+
+    fn for_each_step_in_path<F: FnMut(Handle) -> bool>(
+        &self,
+        name: &str,
+        mut iteratee: F,
+    ) -> Result<bool, Error> {
+        match self.path(name) {
+            Some(steps) => Ok(steps.iter().all(|h| iteratee(*h))),
+            None => Err(Error::MissingPath(name.to_owned())),
+        }
+    }
+
+    fn for_each_step_on_handle<F: FnMut(&str, usize) -> bool>(
+        &self,
+        handle: Handle,
+        mut iteratee: F,
+    ) -> bool {
+        self.steps_on_node(handle.id())
+            .into_iter()
+            .all(|(name, index)| iteratee(name, index))
+    }
+
+    fn get_path_sequence(&self, name: &str) -> Result<String, Error> {
+        self.path_sequence(name)
+    }
+}
+
+impl MutablePathHandleGraph for Graph {
+    fn create_path(&mut self, name: &str) -> Result<(), Error> {
+        self.add_path(name)
+    }
+
+    fn append_step(&mut self, name: &str, handle: Handle) -> Result<(), Error> {
+        Graph::append_step(self, name, handle)
+    }
+
+    fn destroy_path(&mut self, name: &str) -> Result<(), Error> {
+        self.remove_path(name)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::types::Node;
+
+    // GAT -> C|T -> A with an inversion from C to the reverse strand of A
+    fn yeild_graph() -> (Graph, Vec<Handle>) {
+        let nodes = vec![
+            Node::new("GAT", 0, "chr1", Vec::new(), Vec::new()),
+            Node::new("C", 3, "chr1", Vec::new(), Vec::new()),
+            Node::alternative("T", 3, Vec::new(), Vec::new()),
+            Node::new("A", 4, "chr1", Vec::new(), Vec::new()),
+        ];
+        let mut g = Graph::new();
+        let handles: Vec<Handle> = nodes
+            .into_iter()
+            .map(|n| Handle::forward(g.add_node(n)))
+            .collect();
+
+        let edges = [(0, 1), (0, 2), (1, 3), (2, 3)];
+        for (x, y) in edges.iter() {
+            g.add_edge(handles[*x], handles[*y]).unwrap();
+        }
+        g.add_edge(handles[1], handles[3].flip()).unwrap();
+
+        (g, handles)
+    }
+
+    fn neighbours<G: HandleGraph>(g: &G, handle: Handle, go_left: bool) -> Vec<Handle> {
+        let mut found = Vec::new();
+        g.follow_edges(handle, go_left, |h| {
+            found.push(h);
+            true
+        });
+
+        found.sort();
+        found
+    }
+
+    fn sorted(mut handles: Vec<Handle>) -> Vec<Handle> {
+        handles.sort();
+        handles
+    }
+
+    // Written against the trait rather than `Graph`
+    fn total_length<G: HandleGraph>(g: &G) -> usize {
+        let mut length = 0;
+        g.for_each_handle(|h| {
+            length += g.get_length(h);
+            true
+        });
+
+        length
+    }
+
+    #[test]
+    fn test_handles() {
+        let (g, h) = yeild_graph();
+        let id = h[0].id();
+
+        assert!(HandleGraph::has_node(&g, id));
+        assert_eq!(g.get_handle(id, false), h[0]);
+        assert_eq!(g.get_handle(id, true), h[0].flip());
+        assert_eq!(g.get_id(h[0]), id);
+        assert!(g.get_is_reverse(HandleGraph::flip(&g, h[0])));
+        assert_eq!(g.get_node_count(), 4);
+        assert_eq!(total_length(&g), 6);
+
+        let mut ids: Vec<NodeId> = h.iter().map(|h| h.id()).collect();
+        ids.sort();
+        assert_eq!(g.min_node_id(), ids.first().cloned());
+        assert_eq!(g.max_node_id(), ids.last().cloned());
+        assert_eq!(Graph::new().min_node_id(), None);
+    }
+
+    #[test]
+    fn test_get_sequence() {
+        let (g, h) = yeild_graph();
+
+        assert_eq!(g.get_sequence(h[0]), "GAT");
+        assert_eq!(g.get_sequence(h[0].flip()), "ATC");
+        assert_eq!(g.get_length(h[0].flip()), 3);
+    }
+
+    #[test]
+    fn test_follow_edges() {
+        let (g, h) = yeild_graph();
+
+        assert_eq!(neighbours(&g, h[0], false), sorted(vec![h[1], h[2]]));
+        assert_eq!(neighbours(&g, h[0], true), vec![]);
+        assert_eq!(neighbours(&g, h[1], false), sorted(vec![h[3], h[3].flip()]));
+        assert_eq!(neighbours(&g, h[3], true), sorted(vec![h[1], h[2]]));
+
+        // On the reverse strand left and right swap
+        assert_eq!(
+            neighbours(&g, h[0].flip(), true),
+            sorted(vec![h[1].flip(), h[2].flip()])
+        );
+        assert_eq!(neighbours(&g, h[3], false), vec![h[1].flip()]);
+        assert_eq!(neighbours(&g, h[3].flip(), true), vec![h[1]]);
+
+        assert_eq!(g.get_degree(h[1], false), 2);
+        assert_eq!(g.get_degree(h[3], true), 2);
+    }
+
+    #[test]
+    fn test_follow_edges_stops_early() {
+        let (g, h) = yeild_graph();
+        let mut calls = 0;
+
+        let finished = g.follow_edges(h[0], false, |_| {
+            calls += 1;
+            false
+        });
+
+        assert!(!finished);
+        assert_eq!(calls, 1);
+    }
+
+    // The handles on either side of `handle` as (left, right)
+    fn sides(g: &Graph, handle: Handle) -> (Vec<Handle>, Vec<Handle>) {
+        (neighbours(g, handle, true), neighbours(g, handle, false))
+    }
+
+    #[test]
+    fn test_create_handles_and_edges() {
+        let mut g = Graph::new();
+        let x = g.create_handle("GAT");
+        let y = g.create_handle("GAT");
+
+        assert_ne!(x, y);
+        assert_eq!(g.get_sequence(y), "GAT");
+
+        g.create_edge(x, y.flip()).unwrap();
+        g.create_edge(x, y.flip()).unwrap();
+        assert_eq!(g.get_degree(x, false), 1);
+        assert_eq!(neighbours(&g, y, false), vec![x.flip()]);
+
+        g.destroy_edge(y, x.flip()).unwrap();
+        assert_eq!(g.get_degree(x, false), 0);
+        assert_eq!(g.get_degree(y, false), 0);
+
+        let missing = Handle::forward(NodeId::new(99));
+        assert!(g.create_edge(x, missing).is_err());
+    }
+
+    #[test]
+    fn test_destroy_handle() {
+        let (mut g, h) = yeild_graph();
+
+        g.destroy_handle(h[1]).unwrap();
+
+        assert!(!HandleGraph::has_node(&g, h[1].id()));
+        assert_eq!(g.get_node_count(), 3);
+        assert_eq!(neighbours(&g, h[0], false), vec![h[2]]);
+        assert_eq!(neighbours(&g, h[3], true), vec![h[2]]);
+        assert_eq!(g.get_degree(h[3], false), 0);
+        assert!(g.destroy_handle(h[1]).is_err());
+    }
+
+    #[test]
+    fn test_divide_handle() {
+        let mut g = Graph::new();
+        let x = g.create_handle("A");
+        let n = g.create_handle("GATTACA");
+        let y = g.create_handle("C");
+        g.create_edge(x, n).unwrap();
+        g.create_edge(n, y).unwrap();
+
+        let pieces = g.divide_handle(n, &[2, 4]).unwrap();
+        let sequences: Vec<String> = pieces.iter().map(|h| g.get_sequence(*h)).collect();
+
+        assert_eq!(sequences, vec!["GA", "TT", "ACA"]);
+        assert_eq!(sides(&g, pieces[0]), (vec![x], vec![pieces[1]]));
+        assert_eq!(sides(&g, pieces[1]), (vec![pieces[0]], vec![pieces[2]]));
+        assert_eq!(sides(&g, pieces[2]), (vec![pieces[1]], vec![y]));
+
+        assert!(g.divide_handle(pieces[0], &[2]).is_err());
+        assert!(g.divide_handle(pieces[2], &[2, 1]).is_err());
+        assert_eq!(g.get_node_count(), 5);
+    }
+
+    #[test]
+    fn test_divide_reverse_handle() {
+        let mut g = Graph::new();
+        let n = g.create_handle("GATTACA");
+
+        // TGTAATC read from the other strand
+        let pieces = g.divide_handle(n.flip(), &[1]).unwrap();
+        let sequences: Vec<String> = pieces.iter().map(|h| g.get_sequence(*h)).collect();
+
+        assert_eq!(sequences, vec!["T", "GTAATC"]);
+        assert!(pieces.iter().all(|h| h.is_reverse()));
+        assert_eq!(neighbours(&g, pieces[0], false), vec![pieces[1]]);
+    }
+
+    #[test]
+    fn test_truncate_handle() {
+        let mut g = Graph::new();
+        let x = g.create_handle("A");
+        let n = g.create_handle("GATTACA");
+        let y = g.create_handle("C");
+        g.create_edge(x, n).unwrap();
+        g.create_edge(n, y).unwrap();
+
+        let kept = g.truncate_handle(n, true, 3).unwrap();
+        assert_eq!(g.get_sequence(kept), "TACA");
+        assert_eq!(sides(&g, kept), (vec![], vec![y]));
+        assert_eq!(g.get_degree(x, false), 0);
+
+        let kept = g.truncate_handle(kept, false, 1).unwrap();
+        assert_eq!(g.get_sequence(kept), "T");
+        assert_eq!(sides(&g, kept), (vec![], vec![]));
+        assert_eq!(g.get_node_count(), 3);
+
+        assert_eq!(g.truncate_handle(kept, true, 0).unwrap(), kept);
+    }
+
+    #[test]
+    fn test_apply_orientation() {
+        let (mut g, h) = yeild_graph();
+
+        assert_eq!(g.apply_orientation(h[1]).unwrap(), h[1]);
+
+        // C- becomes the forward strand of a G node that keeps its id
+        let c = g.apply_orientation(h[1].flip()).unwrap();
+        assert_eq!(c, h[1]);
+        assert_eq!(g.get_sequence(c), "G");
+        assert_eq!(g.get_node_count(), 4);
+
+        // The edges are the same but read from the other strand of the node
+        assert_eq!(
+            sides(&g, c.flip()),
+            (vec![h[0]], sorted(vec![h[3], h[3].flip()]))
+        );
+        assert_eq!(neighbours(&g, h[0], false), sorted(vec![c.flip(), h[2]]));
+        assert_eq!(neighbours(&g, h[3], false), vec![c]);
+    }
+
+    #[test]
+    fn test_paths() {
+        let (mut g, h) = yeild_graph();
+
+        g.create_path("chr1").unwrap();
+        g.create_path("alt").unwrap();
+        for step in [h[0], h[1], h[3]].iter() {
+            MutablePathHandleGraph::append_step(&mut g, "chr1", *step).unwrap();
+        }
+        MutablePathHandleGraph::append_step(&mut g, "alt", h[3]).unwrap();
+        MutablePathHandleGraph::append_step(&mut g, "alt", h[1].flip()).unwrap();
+
+        assert_eq!(g.get_path_count(), 2);
+        assert_eq!(g.get_step_count("chr1"), Some(3));
+        assert_eq!(g.get_step_count("chr2"), None);
+        assert_eq!(g.get_path_sequence("chr1").unwrap(), "GATCA");
+        assert_eq!(g.get_path_sequence("alt").unwrap(), "AG");
+
+        let mut names = Vec::new();
+        g.for_each_path(|name| {
+            names.push(name.to_owned());
+            true
+        });
+        assert_eq!(names, vec!["alt", "chr1"]);
+
+        let mut visits = Vec::new();
+        g.for_each_step_on_handle(h[1], |name, index| {
+            visits.push((name.to_owned(), index));
+            true
+        });
+        assert_eq!(
+            visits,
+            vec![(String::from("alt"), 1), (String::from("chr1"), 1)]
+        );
+
+        match g.create_path("chr1") {
+            Err(Error::PathExists(name)) => assert_eq!(name, "chr1"),
+            _ => panic!("Expected a path exists error"),
+        }
+        assert!(MutablePathHandleGraph::append_step(&mut g, "chr2", h[0]).is_err());
+
+        g.destroy_path("alt").unwrap();
+        assert!(!PathHandleGraph::has_path(&g, "alt"));
+        assert!(g.destroy_path("alt").is_err());
+    }
+
+    #[test]
+    fn test_paths_follow_changes_to_nodes() {
+        let mut g = Graph::new();
+        let x = g.create_handle("GATTACA");
+        let y = g.create_handle("C");
+        g.create_edge(x, y).unwrap();
+
+        g.create_path("forward").unwrap();
+        MutablePathHandleGraph::append_step(&mut g, "forward", x).unwrap();
+        MutablePathHandleGraph::append_step(&mut g, "forward", y).unwrap();
+        g.create_path("reverse").unwrap();
+        MutablePathHandleGraph::append_step(&mut g, "reverse", y.flip()).unwrap();
+        MutablePathHandleGraph::append_step(&mut g, "reverse", x.flip()).unwrap();
+
+        let pieces = g.divide_handle(x, &[2, 4]).unwrap();
+        assert_eq!(g.get_step_count("forward"), Some(4));
+        assert_eq!(g.get_path_sequence("forward").unwrap(), "GATTACAC");
+        assert_eq!(g.get_path_sequence("reverse").unwrap(), "GTGTAATC");
+
+        g.apply_orientation(pieces[1].flip()).unwrap();
+        assert_eq!(g.get_path_sequence("forward").unwrap(), "GATTACAC");
+        assert_eq!(g.get_path_sequence("reverse").unwrap(), "GTGTAATC");
+
+        let kept = g.truncate_handle(pieces[2], false, 1).unwrap();
+        assert_eq!(g.get_path_sequence("forward").unwrap(), "GATTAC");
+        assert_eq!(g.get_path_sequence("reverse").unwrap(), "GTAATC");
+        assert_eq!(g.get_sequence(kept), "A");
+    }
+}
@@ -0,0 +1,344 @@
+//! Paths for the haplotypes of the samples in variation data
+//!
+//! A haplotype follows the conserved nodes of its sequence and, through each site,
+//! the allele its genotypes give it. Its path is named `sample#haplotype#sequence`
+//! as in PanSN with the haplotypes of a sample numbered from 1 in the order of its genotypes.
+//! A path that doesn't start at the start of the sequence has the offset it starts at
+//! after it like `sample#haplotype#sequence[offset]`.
+
+use std::collections::HashMap;
+
+// VCF
+use vcf::VCFRecord;
+
+use crate::error::Error;
+use crate::graph::types::{Graph, Handle, NodeId};
+use crate::io::variation::{self, Alleles, Genotype, Replacement, Unphased};
+
+/// A part of a sequence in the graph in the order they are on the sequence
+pub enum Part {
+    // A node of the reference between two sites
+    Conserved(NodeId),
+    // The nodes of the alleles of a site keyed by their sequence, None for an empty allele
+    Site(Alleles, HashMap<String, Option<NodeId>>),
+}
+
+// What a haplotype has at a record other than its reference allele
+enum Call {
+    Allele(Replacement),
+    // An allele we can't follow at the position of the site of its record
+    Unknown(usize),
+}
+
+impl Call {
+    fn position(&self) -> usize {
+        match self {
+            Call::Allele(replacement) => replacement.start,
+            Call::Unknown(position) => *position,
+        }
+    }
+}
+
+/// Add a path to `graph` for each haplotype of each of `samples` through the `parts` of a sequence
+/// that start at `start` on it.
+///
+/// `samples` are the names of the samples in the records.
+/// `records` are the records the sites were built out of, each with the position of its site.
+/// `unphased` says what to do at a site where we can't tell which allele a haplotype has.
+/// Returns a warning for each haplotype that is left out.
+pub fn thread_haplotypes(
+    graph: &mut Graph,
+    sequence_id: &str,
+    start: usize,
+    parts: &[Part],
+    records: &[(&VCFRecord, usize)],
+    samples: &[&str],
+    unphased: Unphased,
+) -> Result<Vec<Error>, Error> {
+    let mut warnings: Vec<Error> = Vec::new();
+    let mut replacements: Vec<Vec<Option<Replacement>>> = Vec::new();
+    for (record, _) in records {
+        replacements.push(variation::replacements(record)?);
+    }
+
+    for sample in samples {
+        let mut genotypes: Vec<Option<Genotype>> = Vec::new();
+        for (record, _) in records {
+            genotypes.push(Genotype::new(record, sample)?);
+        }
+
+        let ploidy = genotypes
+            .iter()
+            .flatten()
+            .map(|genotype| genotype.alleles.len())
+            .max()
+            .unwrap_or(0);
+
+        for haplotype in 0..ploidy {
+            let mut calls: Vec<Call> = Vec::new();
+
+            for ((genotype, alleles), (record, position)) in
+                genotypes.iter().zip(replacements.iter()).zip(records)
+            {
+                let allele = match genotype {
+                    Some(genotype) if genotype.phased => {
+                        genotype.alleles.get(haplotype).cloned().flatten()
+                    }
+                    _ => None,
+                };
+
+                let replacement = match allele {
+                    Some(0) => continue,
+                    // The haplotype follows the deletion at the record it's on
+                    Some(allele)
+                        if record.alternative.get(allele - 1).map(String::as_str)
+                            == Some(variation::SPANNING_DELETION) =>
+                    {
+                        continue
+                    }
+                    Some(allele) => alleles.get(allele - 1).cloned().flatten(),
+                    None => None,
+                };
+
+                calls.push(match replacement {
+                    Some(replacement) => Call::Allele(replacement),
+                    None => Call::Unknown(*position),
+                });
+            }
+
+            calls.sort_by_key(Call::position);
+
+            let name = format!("{}#{}#{}", sample, haplotype + 1, sequence_id);
+            let blocks = match follow(start, parts, &calls, unphased) {
+                Some(blocks) => blocks,
+                None => {
+                    warnings.push(Error::Unsupported(format!(
+                        "haplotype {} is left out, it has an allele we can't follow",
+                        name
+                    )));
+                    continue;
+                }
+            };
+
+            // Blocks that don't start the sequence are named after the offset they start at
+            for (offset, steps) in blocks.into_iter().filter(|(_, steps)| !steps.is_empty()) {
+                let name = match offset {
+                    0 => name.clone(),
+                    _ => format!("{}[{}]", name, offset),
+                };
+
+                graph.add_path(&name)?;
+                for step in steps {
+                    graph.append_step(&name, step)?;
+                }
+            }
+        }
+    }
+
+    Ok(warnings)
+}
+
+// The steps of a haplotype through `parts` from `start` given its `calls` ordered by position.
+// The steps are in blocks that each start at an offset on the sequence,
+// there's more than one when `unphased` splits the haplotype.
+// None when the haplotype is left out.
+fn follow(
+    start: usize,
+    parts: &[Part],
+    calls: &[Call],
+    unphased: Unphased,
+) -> Option<Vec<(usize, Vec<Handle>)>> {
+    let mut blocks: Vec<(usize, Vec<Handle>)> = vec![(start, Vec::new())];
+    let mut calls = calls.iter().peekable();
+
+    for part in parts {
+        let (site, nodes) = match part {
+            Part::Conserved(id) => {
+                blocks.last_mut()?.1.push(Handle::forward(*id));
+                continue;
+            }
+            Part::Site(site, nodes) => (site, nodes),
+        };
+
+        // The calls on this site
+        let mut changes: Vec<&Replacement> = Vec::new();
+        let mut known = true;
+        while let Some(call) = calls.peek() {
+            if call.position() != site.start && call.position() >= site.end() {
+                break;
+            }
+
+            match call {
+                Call::Allele(replacement) => changes.push(replacement),
+                Call::Unknown(_) => known = false,
+            }
+            calls.next();
+        }
+
+        let node = if known {
+            apply(site, &changes).and_then(|allele| nodes.get(&allele))
+        } else {
+            None
+        };
+
+        match (node, unphased) {
+            (Some(node), _) => blocks.last_mut()?.1.extend(node.map(Handle::forward)),
+            (None, Unphased::Reference) => {
+                let reference = nodes.get(&site.reference).cloned().flatten();
+                blocks.last_mut()?.1.extend(reference.map(Handle::forward));
+            }
+            (None, Unphased::Split) => blocks.push((site.end(), Vec::new())),
+            (None, Unphased::Skip) => return None,
+        }
+    }
+
+    Some(blocks)
+}
+
+// The allele of a haplotype at a site, the reference of the site with the changes it makes.
+// None when the changes overlap.
+fn apply(site: &Alleles, changes: &[&Replacement]) -> Option<String> {
+    let mut allele = String::new();
+    let mut position = site.start;
+
+    for change in changes {
+        if change.start < position || change.end > site.end() {
+            return None;
+        }
+
+        allele.push_str(&site.reference[position - site.start..change.start - site.start]);
+        allele.push_str(&change.sequence);
+        position = change.end;
+    }
+
+    allele.push_str(&site.reference[position - site.start..]);
+
+    Some(allele)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::graph::ConstructOptions;
+    use crate::io::reference;
+    use std::io::Cursor;
+
+    const FASTA: &str = ">chr1\nGATCATCGGTTACACAGCTA\n";
+
+    // GATC A|G TCGGT TA|.|TC CACAG .|CC CTA
+    const VCF: &str = "\
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO	FORMAT	A	B	C
+chr1	5	snv	A	G	.	PASS	.	GT	0|1	1|1	0|0
+chr1	10	del	TTA	T	.	PASS	.	GT	1|0	0/1	1|1
+chr1	12	overlap	A	C	.	PASS	.	GT	0|1	0|0	1|0
+chr1	17	ins	G	GCC	.	PASS	.	GT	0|1	.|.	0|0
+";
+
+    // The names and sequences of the haplotype paths of a graph ordered by name
+    fn haplotypes(unphased: Option<Unphased>) -> Vec<(String, String)> {
+        haplotypes_in(VCF, unphased)
+    }
+
+    fn haplotypes_in(vcf: &str, unphased: Option<Unphased>) -> Vec<(String, String)> {
+        let options = ConstructOptions {
+            haplotypes: unphased,
+            ..ConstructOptions::default()
+        };
+        let (graph, _) =
+            reference::construct_with(Cursor::new(FASTA), Cursor::new(vcf), &options).unwrap();
+
+        let mut paths: Vec<(String, String)> = graph
+            .paths()
+            .filter(|(name, _)| name.contains('#'))
+            .map(|(name, _)| (name.to_owned(), graph.path_sequence(name).unwrap()))
+            .collect();
+        paths.sort();
+
+        paths
+    }
+
+    fn owned(paths: &[(&str, &str)]) -> Vec<(String, String)> {
+        paths
+            .iter()
+            .map(|(name, sequence)| (String::from(*name), String::from(*sequence)))
+            .collect()
+    }
+
+    #[test]
+    fn test_haplotypes_follow_their_alleles() {
+        // B isn't phased at the deletion or has no alleles at the insertion.
+        // The first haplotype of C has both the deletion and the SNP it overlaps.
+        assert_eq!(
+            haplotypes(Some(Unphased::Skip)),
+            owned(&[
+                ("A#1#chr1", "GATCATCGGTCACAGCTA"),
+                ("A#2#chr1", "GATCGTCGGTTCCACAGCCCTA"),
+                ("C#2#chr1", "GATCATCGGTCACAGCTA"),
+            ])
+        );
+
+        assert!(haplotypes(None).is_empty());
+
+        // The haplotypes left out are reported
+        let options = ConstructOptions {
+            haplotypes: Some(Unphased::Skip),
+            ..ConstructOptions::default()
+        };
+        let (_, warnings) =
+            reference::construct_with(Cursor::new(FASTA), Cursor::new(VCF), &options).unwrap();
+        let skipped: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(skipped.len(), 3);
+        assert!(skipped[0].contains("B#1#chr1"), "{}", skipped[0]);
+    }
+
+    #[test]
+    fn test_haplotypes_follow_spanning_deletions() {
+        // The first haplotype has the deletion so it has `*` at the SNP it overlaps
+        let vcf = "\
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO	FORMAT	A
+chr1	10	del	TTA	T	.	PASS	.	GT	1|0
+chr1	12	overlap	A	C,*	.	PASS	.	GT	2|1
+";
+        assert_eq!(
+            haplotypes_in(vcf, Some(Unphased::Skip)),
+            owned(&[
+                ("A#1#chr1", "GATCATCGGTCACAGCTA"),
+                ("A#2#chr1", "GATCATCGGTTCCACAGCTA"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_haplotypes_through_unphased_genotypes() {
+        let reference = haplotypes(Some(Unphased::Reference));
+        assert_eq!(reference.len(), 6);
+        assert_eq!(
+            reference[2..5],
+            owned(&[
+                ("B#1#chr1", "GATCGTCGGTTACACAGCTA"),
+                ("B#2#chr1", "GATCGTCGGTTACACAGCTA"),
+                ("C#1#chr1", "GATCATCGGTTACACAGCTA"),
+            ])[..]
+        );
+
+        // The blocks after the first are named after where they start
+        let split = haplotypes(Some(Unphased::Split));
+        assert_eq!(split.len(), 11);
+        assert_eq!(
+            split[2..],
+            owned(&[
+                ("B#1#chr1", "GATCGTCGGT"),
+                ("B#1#chr1[12]", "CACAG"),
+                ("B#1#chr1[17]", "CTA"),
+                ("B#2#chr1", "GATCGTCGGT"),
+                ("B#2#chr1[12]", "CACAG"),
+                ("B#2#chr1[17]", "CTA"),
+                ("C#1#chr1", "GATCATCGGT"),
+                ("C#1#chr1[12]", "CACAGCTA"),
+                ("C#2#chr1", "GATCATCGGTCACAGCTA"),
+            ])[..]
+        );
+    }
+}
@@ -1,5 +1,10 @@
 pub mod macros;
-mod dot;
+pub mod dot;
+pub mod gfa;
+#[allow(clippy::module_inception)]
 pub mod graph;
+pub mod handlegraph;
+pub mod haplotypes;
 pub mod types;
+pub mod update;
 pub mod utils;
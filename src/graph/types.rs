@@ -1,78 +1,454 @@
 use hex;
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryInto;
 use std::fmt;
+use std::str::FromStr;
 
+/// The id of a node in its graph.
+/// The graph hands out ids in the order nodes are added to it starting at 1 as vg does.
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct NodeId(u64);
+
+/// A hash of the content of a node that is the same in any graph the node is in
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord)]
+pub struct NodeHash([u8; 32]);
+
+/// How the hashes of the nodes of a graph are computed
+///
+/// The hash of a node is that of its segment followed by its offset as `position` writes it.
+/// Graphs record their scheme so that the same nodes always get the same hashes.
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, Serialize, Deserialize)]
+pub struct HashScheme {
+    pub algorithm: HashAlgorithm,
+    pub position: PositionEncoding,
+}
+
+/// The function that hashes the nodes in a [`HashScheme`]
+///
+/// [`HashScheme`]: struct.HashScheme.html
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    Sha256,
+    // Cryptographic like SHA 256 but faster
+    Blake3,
+    // Not cryptographic and the fastest of them
+    Xxh3,
+}
+
+/// How the offset of a node goes into its hash
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, Serialize, Deserialize)]
+pub enum PositionEncoding {
+    // A `+` and the offset in decimal e.g. `+23`
+    Text,
+    // The offset as 8 little endian bytes
+    Binary,
+    // Only the segment, for de novo graphs whose nodes have no position.
+    // Nodes with the same segment get the same hash so it can't tell them apart.
+    None,
+}
+
+/// The strand a node is read on
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Orientation {
+    Forward,
+    Reverse,
+}
+
+/// A node read on one of its strands as in [libhandlegraph]
+///
+/// [libhandlegraph]: https://github.com/vgteam/libhandlegraph
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Handle {
+    id: NodeId,
+    orientation: Orientation,
+}
+
+/// An edge from the end of one handle to the start of another.
+/// `Edge(x, y)` and `Edge(y.flip(), x.flip())` are the same edge read on the other strand.
 #[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
-pub struct NodeId([u8; 32]);
+pub struct Edge(pub Handle, pub Handle);
 
-type EdgeList = Vec<NodeId>;
+// The handles on one side of a node.
+// `nodes_right` holds the handles h of the edges `+node -> h`
+// and `nodes_left` the handles h of the edges `h -> +node`.
+type EdgeList = Vec<Handle>;
 
 /// A vertex or node in a variation graph
-#[derive(Debug, PartialEq, Clone, Eq)]
-pub struct Node<'a> {
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
+pub struct Node {
     // Required: the piece of sequence associated with the node. A string of alphabet A, T, C, and G.
-    pub segment: &'a str,
+    // Owned so that a graph can outlive the reference and VCF it was built from.
+    pub segment: String,
 
     // Offset: When a graph is built out of a reference this is the position of the start of the segment on that reference
     offset: usize,
 
-    // Required: Unique identifier of each node.
-    // Currently, a SHA 256 hash of the concatenation of segment, “+” and offset
-    // TODO: Not require an offset for de novo graphs to be built
+    // Required: Unique identifier of each node in its graph.
+    // Given by the graph when the node is added to it.
     pub id: NodeId,
 
+    // Optional: hash of the segment and offset given by the scheme of the graph the node is in
+    hash: Option<NodeHash>,
+
+    // Whether the graph the node is added to should hash it.
+    // Nodes with a position are hashed, those read back with their hash or without one aren't.
+    #[serde(skip)]
+    needs_hash: bool,
+
     // Optional: ID of the reference from which we got this node
-    reference: &'a str,
+    // None for nodes that aren't on the reference such as those of alternative alleles
+    reference: Option<String>,
 
-    // Required: The edges to the right of this node
+    // Required: The edges on the right side (end) of this node
     pub nodes_right: EdgeList,
 
-    // Required: The edges to the left of this node
-    nodes_left: EdgeList,
+    // Required: The edges on the left side (start) of this node
+    pub nodes_left: EdgeList,
 }
 
+use crate::error::Error;
 use crate::graph::utils;
 
-impl<'a> Node<'a> {
-    pub fn new(
-        segment: &'a str,
+impl Node {
+    pub fn new<S: Into<String>, R: Into<String>>(
+        segment: S,
         offset: usize,
-        reference: &'a str,
+        reference: R,
         nodes_right: EdgeList,
         nodes_left: EdgeList,
     ) -> Self {
-        let id = utils::gen_node_hash(segment, offset).unwrap();
-        let id = NodeId(id);
+        Node::build(
+            segment.into(),
+            offset,
+            Some(reference.into()),
+            nodes_right,
+            nodes_left,
+        )
+    }
 
+    // A node that isn't on the reference e.g. an alternative allele at `offset`
+    pub fn alternative<S: Into<String>>(
+        segment: S,
+        offset: usize,
+        nodes_right: EdgeList,
+        nodes_left: EdgeList,
+    ) -> Self {
+        Node::build(segment.into(), offset, None, nodes_right, nodes_left)
+    }
+
+    // The hash is left to the graph the node is added to
+    fn build(
+        segment: String,
+        offset: usize,
+        reference: Option<String>,
+        nodes_right: EdgeList,
+        nodes_left: EdgeList,
+    ) -> Self {
+        let mut n = Node::with_hash(None, segment, offset, reference, nodes_right, nodes_left);
+        n.needs_hash = true;
+
+        n
+    }
+
+    // A node whose hash we already know or that has none e.g. one read back from a file
+    pub(crate) fn with_hash(
+        hash: Option<NodeHash>,
+        segment: String,
+        offset: usize,
+        reference: Option<String>,
+        nodes_right: EdgeList,
+        nodes_left: EdgeList,
+    ) -> Self {
         Node {
             segment,
             offset,
-            id,
+            id: NodeId(0),
+            hash,
+            needs_hash: false,
             reference,
             nodes_left,
             nodes_right,
         }
     }
 
-    pub fn raw_id(&self) -> &[u8; 32] {
-        &self.id.0
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn reference(&self) -> Option<&str> {
+        self.reference.as_deref()
+    }
+
+    // None until the node is added to a graph
+    pub fn hash(&self) -> Option<NodeHash> {
+        self.hash
+    }
+
+    // The edges on either side of this node.
+    // Edges on the left side are read from the reverse strand of this node.
+    pub fn edges(&self) -> Vec<Edge> {
+        let forward = Handle::forward(self.id);
+        let right = self.nodes_right.iter().map(|h| Edge(forward, *h));
+        let left = self
+            .nodes_left
+            .iter()
+            .map(|h| Edge(forward.flip(), h.flip()));
+
+        right.chain(left).collect()
+    }
+}
+
+impl Handle {
+    pub fn new(id: NodeId, orientation: Orientation) -> Self {
+        Handle { id, orientation }
+    }
+
+    pub fn forward(id: NodeId) -> Self {
+        Handle::new(id, Orientation::Forward)
+    }
+
+    pub fn reverse(id: NodeId) -> Self {
+        Handle::new(id, Orientation::Reverse)
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    pub fn is_reverse(&self) -> bool {
+        self.orientation == Orientation::Reverse
+    }
+
+    // The same node read on the other strand
+    pub fn flip(&self) -> Self {
+        match self.orientation {
+            Orientation::Forward => Handle::reverse(self.id),
+            Orientation::Reverse => Handle::forward(self.id),
+        }
+    }
+}
+
+// Written the way GFA writes a path step e.g. `s1+`
+impl std::fmt::Display for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let strand = if self.is_reverse() { '-' } else { '+' };
+        write!(f, "{}{}", self.id, strand)
+    }
+}
+
+impl Edge {
+    // The same edge read on the other strand
+    pub fn flip(&self) -> Self {
+        Edge(self.1.flip(), self.0.flip())
+    }
+
+    // Pick one of the two ways to write an edge so that it can be compared and written once.
+    // Prefer leaving a forward handle and then the smaller one.
+    pub fn canonical(&self) -> Self {
+        let flipped = self.flip();
+        let key = |e: &Edge| (e.0.is_reverse(), e.0, e.1);
+
+        if key(&flipped) < key(self) {
+            flipped
+        } else {
+            *self
+        }
+    }
+}
+
+impl NodeId {
+    pub fn new(id: u64) -> Self {
+        NodeId(id)
+    }
+
+    pub fn value(&self) -> u64 {
+        self.0
     }
 }
 
 impl std::fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Parse the number of an id, the inverse of `Display`
+impl FromStr for NodeId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse() {
+            Ok(id) => Ok(NodeId(id)),
+            Err(_) => Err(Error::Malformed(format!("{} isn't a node id", s))),
+        }
+    }
+}
+
+impl std::fmt::Display for NodeHash {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", hex::encode(self.0))
     }
 }
 
-impl Borrow<[u8; 32]> for NodeId {
+impl NodeHash {
+    // The hash of a node with `segment` at `offset` in the default scheme
+    pub fn new(segment: &str, offset: usize) -> Self {
+        HashScheme::default().hash(segment, offset)
+    }
+}
+
+impl HashScheme {
+    pub fn new(algorithm: HashAlgorithm, position: PositionEncoding) -> Self {
+        HashScheme {
+            algorithm,
+            position,
+        }
+    }
+
+    // Whether nodes with the same segment at different offsets get different hashes
+    pub fn encodes_position(&self) -> bool {
+        self.position != PositionEncoding::None
+    }
+
+    // The hash of a node with `segment` at `offset`
+    pub fn hash(&self, segment: &str, offset: usize) -> NodeHash {
+        let mut msg = segment.as_bytes().to_vec();
+
+        match self.position {
+            PositionEncoding::Text => msg.extend_from_slice(format!("+{}", offset).as_bytes()),
+            PositionEncoding::Binary => msg.extend_from_slice(&(offset as u64).to_le_bytes()),
+            PositionEncoding::None => {}
+        }
+
+        match self.algorithm {
+            HashAlgorithm::Sha256 => NodeHash(utils::sha256(&msg).unwrap()),
+            HashAlgorithm::Blake3 => NodeHash(utils::blake3(&msg)),
+            HashAlgorithm::Xxh3 => NodeHash(utils::xxh3(&msg)),
+        }
+    }
+}
+
+// SHA 256 of the segment, “+” and the offset, the scheme of graphs from before there was a choice
+impl Default for HashScheme {
+    fn default() -> Self {
+        HashScheme::new(HashAlgorithm::Sha256, PositionEncoding::Text)
+    }
+}
+
+// Written as `algorithm:position` e.g. `sha256:text`
+impl std::fmt::Display for HashScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let algorithm = match self.algorithm {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Xxh3 => "xxh3",
+        };
+        let position = match self.position {
+            PositionEncoding::Text => "text",
+            PositionEncoding::Binary => "binary",
+            PositionEncoding::None => "none",
+        };
+
+        write!(f, "{}:{}", algorithm, position)
+    }
+}
+
+// Parse a scheme written by `Display`, the position defaults to text e.g. `blake3`
+impl FromStr for HashScheme {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || Error::Malformed(format!("{} isn't a hash scheme", s));
+        let mut parts = s.splitn(2, ':');
+
+        let algorithm = match parts.next() {
+            Some("sha256") => HashAlgorithm::Sha256,
+            Some("blake3") => HashAlgorithm::Blake3,
+            Some("xxh3") => HashAlgorithm::Xxh3,
+            _ => return Err(malformed()),
+        };
+        let position = match parts.next() {
+            None | Some("text") => PositionEncoding::Text,
+            Some("binary") => PositionEncoding::Binary,
+            Some("none") => PositionEncoding::None,
+            _ => return Err(malformed()),
+        };
+
+        Ok(HashScheme::new(algorithm, position))
+    }
+}
+
+// Parse the hex representation of a hash, the inverse of `Display`
+impl FromStr for NodeHash {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || Error::Malformed(format!("{} isn't a node hash", s));
+        let bytes = hex::decode(s).map_err(|_| malformed())?;
+        let hash: [u8; 32] = bytes[..].try_into().map_err(|_| malformed())?;
+
+        Ok(NodeHash(hash))
+    }
+}
+
+// Serialize hashes as bytes rather than as a sequence of 32 numbers to keep them compact
+impl Serialize for NodeHash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeHash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NodeHashVisitor;
+
+        impl<'de> Visitor<'de> for NodeHashVisitor {
+            type Value = NodeHash;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "32 bytes")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<NodeHash, E> {
+                match v.try_into() {
+                    Ok(hash) => Ok(NodeHash(hash)),
+                    Err(_) => Err(E::invalid_length(v.len(), &self)),
+                }
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<NodeHash, A::Error> {
+                let mut hash = [0u8; 32];
+
+                for (i, byte) in hash.iter_mut().enumerate() {
+                    *byte = match seq.next_element()? {
+                        Some(byte) => byte,
+                        None => return Err(de::Error::invalid_length(i, &self)),
+                    };
+                }
+
+                Ok(NodeHash(hash))
+            }
+        }
+
+        deserializer.deserialize_bytes(NodeHashVisitor)
+    }
+}
+
+impl Borrow<[u8; 32]> for NodeHash {
     fn borrow(&self) -> &[u8; 32] {
         &self.0
     }
 }
 
-impl<'a> std::fmt::Display for Node<'a> {
+impl std::fmt::Display for Node {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.id)
     }
@@ -81,43 +457,145 @@ impl<'a> std::fmt::Display for Node<'a> {
 // TODO: link id and node
 /// A [variation graph] is a HashMap of [`id`] to [`Node`].
 /// This has several advantages for us:
-///  - duplicates: we get to avoid duplicates for "free"
+///  - duplicates: we get to avoid duplicates for "free" by looking up the hash of a node
 ///
 /// [variation graph]: https://blog.urbanslug.com/posts/2019-06-22-Introduction-to-Variation-Graphs.html
 /// [`id`]: ../../vg/graph/struct.Node.html
 /// [`Node`]: ../../vg/graph/struct.Node.html
 ///
 /// Attempting compatibility with https://github.com/vgteam/libhandlegraph
-type InternalGraph<'a> = HashMap<NodeId, Node<'a>>;
-pub struct Graph<'a>(InternalGraph<'a>);
+type InternalGraph = HashMap<NodeId, Node>;
+
+// The steps of each path keyed by the name of the path
+type Paths = BTreeMap<String, Vec<Handle>>;
+
+#[derive(Debug, PartialEq)]
+pub struct Graph {
+    nodes: InternalGraph,
+
+    // The id of the node with each hash.
+    // Nodes with the same sequence at the same offset share a hash, it maps to the first of them.
+    hashes: HashMap<NodeHash, NodeId>,
+
+    // The id the next node added to the graph gets
+    next_id: u64,
+
+    // How the nodes of the graph are hashed
+    scheme: HashScheme,
+
+    // Named walks through the graph such as a reference or a haplotype
+    paths: Paths,
+}
 
-impl<'a> Graph<'a> {
+// The nodes already hold their ids so they are serialized as a sequence rather than a map
+#[derive(Serialize)]
+struct SerializedGraph<'g> {
+    next_id: u64,
+    scheme: HashScheme,
+    nodes: Vec<&'g Node>,
+    paths: &'g Paths,
+}
+
+#[derive(Deserialize)]
+struct DeserializedGraph {
+    next_id: u64,
+    scheme: HashScheme,
+    nodes: Vec<Node>,
+    paths: Paths,
+}
+
+impl Serialize for Graph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut nodes: Vec<&Node> = self.nodes.values().collect();
+        nodes.sort_by_key(|n| n.id);
+
+        SerializedGraph {
+            next_id: self.next_id,
+            scheme: self.scheme,
+            nodes,
+            paths: &self.paths,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Graph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let DeserializedGraph {
+            next_id,
+            scheme,
+            nodes,
+            paths,
+        } = DeserializedGraph::deserialize(deserializer)?;
+        let mut g = Graph::with_scheme(scheme);
+
+        for n in nodes {
+            g.insert_node(n).map_err(de::Error::custom)?;
+        }
+        g.next_id = g.next_id.max(next_id);
+
+        // Paths can only visit nodes in the graph
+        for (name, steps) in paths.iter() {
+            if let Some(h) = steps.iter().find(|h| !g.has_node(h.id)) {
+                return Err(de::Error::custom(format!(
+                    "path {} visits missing node {}",
+                    name, h.id
+                )));
+            }
+        }
+        g.paths = paths;
+
+        Ok(g)
+    }
+}
+
+impl Default for Graph {
+    fn default() -> Self {
+        Graph::new()
+    }
+}
+
+impl Graph {
     // Create a new empty graph
-    pub fn new() -> Graph<'a> {
-        let vg: InternalGraph<'a> = HashMap::new();
-        Graph(vg)
+    pub fn new() -> Graph {
+        Graph::with_scheme(HashScheme::default())
     }
 
-    pub fn hashmap(&self) -> &InternalGraph<'a> {
-        &self.0
+    // Create a new empty graph whose nodes are hashed with `scheme`
+    pub fn with_scheme(scheme: HashScheme) -> Graph {
+        Graph {
+            nodes: HashMap::new(),
+            hashes: HashMap::new(),
+            next_id: 1,
+            scheme,
+            paths: BTreeMap::new(),
+        }
+    }
+
+    pub fn scheme(&self) -> HashScheme {
+        self.scheme
+    }
+
+    pub fn hashmap(&self) -> &InternalGraph {
+        &self.nodes
     }
 
     // Check whether a node exists
-    fn has_node(&self, id: NodeId) -> bool {
-        let hashmap = &self.0;
+    pub fn has_node(&self, id: NodeId) -> bool {
+        let hashmap = &self.nodes;
         hashmap.contains_key(&id)
     }
 
     // Get an immutable reference to a node
-    fn get_node(&self, id: NodeId) -> Option<&Node<'a>> {
-        let hashmap = &self.0;
+    pub fn get_node(&self, id: NodeId) -> Option<&Node> {
+        let hashmap = &self.nodes;
 
         hashmap.get(&id)
     }
 
     // Get a mutable reference to the node
-    fn get_node_mut(&mut self, id: NodeId) -> Option<&mut Node<'a>> {
-        let hashmap = &mut self.0;
+    pub(crate) fn get_node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        let hashmap = &mut self.nodes;
 
         hashmap.get_mut(&id)
     }
@@ -127,67 +605,477 @@ impl<'a> Graph<'a> {
 
     // We want the graph to own its nodes.
     // TODO: why must a graph own its nodes?
-    pub fn add_node(&mut self, n: Node<'a>) {
-        let hashmap = &mut self.0;
+    // Hashes the node with the scheme of the graph, gives it the next id and returns it.
+    // Every node gets an id of its own even when a node with its hash is already in the graph:
+    // a reference node and an allele, or alleles on two sequences, can share a segment and offset.
+    pub fn add_node(&mut self, mut n: Node) -> NodeId {
+        if n.needs_hash {
+            n.hash = Some(self.scheme.hash(&n.segment, n.offset));
+            n.needs_hash = false;
+        }
+
+        n.id = self.fresh_id();
+        self.insert(n)
+    }
+
+    // Add a node that already has an id e.g. one read back from a file
+    pub(crate) fn insert_node(&mut self, n: Node) -> Result<NodeId, Error> {
+        if self.has_node(n.id) {
+            return Err(Error::Malformed(format!("duplicate node {}", n.id)));
+        }
+
+        Ok(self.insert(n))
+    }
+
+    fn insert(&mut self, n: Node) -> NodeId {
         let id = n.id;
+        self.next_id = self.next_id.max(id.0 + 1);
+
+        if let Some(hash) = n.hash {
+            self.hashes.entry(hash).or_insert(id);
+        }
+
+        let hashmap = &mut self.nodes;
         hashmap.insert(id, n);
+
+        id
+    }
+
+    // Take a node out of the graph without touching its neighbours or paths
+    fn take(&mut self, id: NodeId) -> Option<Node> {
+        let n = self.nodes.remove(&id)?;
+
+        if let Some(hash) = n.hash {
+            if self.hashes.get(&hash) == Some(&id) {
+                self.hashes.remove(&hash);
+            }
+        }
+
+        Some(n)
     }
 
-    // Does an edge from x to y exist?
-    fn edge_exists(&self, x: NodeId, y: NodeId) -> bool {
-        // Does y exist in x's right nodes
-        let x_right_nodes = &self.get_node(x).unwrap().nodes_right;
+    fn fresh_id(&mut self) -> NodeId {
+        let id = NodeId(self.next_id);
+        self.next_id += 1;
 
-        // Does x exist in y's right nodes
-        let y_left_nodes = &self.get_node(y).unwrap().nodes_left;
+        id
+    }
 
-        // Check that the nodes point to each other
-        x_right_nodes.contains(&y) && y_left_nodes.contains(&x)
+    // The id of the node with `hash`
+    pub fn id_of_hash(&self, hash: NodeHash) -> Option<NodeId> {
+        self.hashes.get(&hash).cloned()
     }
 
-    // Add an edge from x to y
-    // TODO: Use Errors and the Result type
-    fn add_edge(&self, x: &mut Node<'a>, y: &mut Node<'a>) {
-        if self.has_node(x.id) && self.has_node(y.id) {
-            // Add the id of x to the left nodes list of y
-            y.nodes_left.push(x.id);
+    // The id of the node with `segment` at `offset`,
+    // always None when the scheme doesn't hash positions since it can't tell which node that is
+    pub fn id_of_node(&self, segment: &str, offset: usize) -> Option<NodeId> {
+        self.id_of_node_hash(self.scheme.hash(segment, offset))
+    }
 
-            // Add the id of y to the right nodes list of x
-            x.nodes_right.push(y.id);
+    fn id_of_node_hash(&self, hash: NodeHash) -> Option<NodeId> {
+        if self.scheme.encodes_position() {
+            self.id_of_hash(hash)
         } else {
-            if !self.has_node(x.id) && !self.has_node(y.id) {
-                // Both x and y aren't in the graph
-                panic!("Both nodes {} {} aren't in the graph", x.id, y.id)
-            } else if !self.has_node(x.id) {
-                // x isn't in the graph
-                panic!("Node {} isn't in the graph", x.id)
-            } else {
-                // y isn't in the graph
-                panic!("Node {} isn't in the graph", y.id)
+            None
+        }
+    }
+
+    // Move all the nodes and paths of `other` into this graph.
+    // The nodes of `other` get new ids that follow the ones in this graph
+    // and are hashed again if `other` hashes nodes another way.
+    // A path of `other` replaces a path of the same name in this graph.
+    pub fn merge(&mut self, other: Graph) {
+        let rehash = other.scheme != self.scheme;
+
+        let mut ids: Vec<NodeId> = other.nodes.keys().cloned().collect();
+        ids.sort();
+
+        let new_ids: HashMap<NodeId, NodeId> =
+            ids.into_iter().map(|id| (id, self.fresh_id())).collect();
+        let renumber = |h: &Handle| Handle::new(new_ids[&h.id], h.orientation);
+
+        for (_, mut n) in other.nodes {
+            n.id = new_ids[&n.id];
+            if rehash && n.hash.is_some() {
+                n.hash = Some(self.scheme.hash(&n.segment, n.offset));
+            }
+            n.nodes_left = n.nodes_left.iter().map(renumber).collect();
+            n.nodes_right = n.nodes_right.iter().map(renumber).collect();
+            self.insert(n);
+        }
+
+        for (name, steps) in other.paths {
+            self.paths
+                .insert(name, steps.iter().map(renumber).collect());
+        }
+    }
+
+    // Paths
+    // ---
+
+    pub fn has_path(&self, name: &str) -> bool {
+        self.paths.contains_key(name)
+    }
+
+    // The steps of a path in order
+    pub fn path(&self, name: &str) -> Option<&[Handle]> {
+        self.paths.get(name).map(|steps| &steps[..])
+    }
+
+    // The names and steps of the paths ordered by name
+    pub fn paths(&self) -> impl Iterator<Item = (&str, &[Handle])> {
+        self.paths
+            .iter()
+            .map(|(name, steps)| (name.as_str(), &steps[..]))
+    }
+
+    // Add an empty path
+    pub fn add_path(&mut self, name: &str) -> Result<(), Error> {
+        if self.has_path(name) {
+            return Err(Error::PathExists(name.to_owned()));
+        }
+
+        self.paths.insert(name.to_owned(), Vec::new());
+
+        Ok(())
+    }
+
+    // Add a step to the end of a path
+    pub fn append_step(&mut self, name: &str, handle: Handle) -> Result<(), Error> {
+        if !self.has_node(handle.id) {
+            return Err(Error::MissingNode(handle.id));
+        }
+
+        match self.paths.get_mut(name) {
+            Some(steps) => {
+                steps.push(handle);
+                Ok(())
             }
+            None => Err(Error::MissingPath(name.to_owned())),
+        }
+    }
+
+    pub fn remove_path(&mut self, name: &str) -> Result<Vec<Handle>, Error> {
+        match self.paths.remove(name) {
+            Some(steps) => Ok(steps),
+            None => Err(Error::MissingPath(name.to_owned())),
         }
     }
 
-    pub fn add_edge_from_id(&mut self, x: NodeId, y: NodeId) {
-        if self.has_node(x) && self.has_node(y) {
-            // Add the id of x to the left nodes list of y
-            self.get_node_mut(y).unwrap().nodes_left.push(x);
+    // The paths that visit a node and the index of each visit ordered by path name
+    pub fn steps_on_node(&self, id: NodeId) -> Vec<(&str, usize)> {
+        self.paths()
+            .flat_map(|(name, steps)| {
+                steps
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, h)| h.id == id)
+                    .map(move |(index, _)| (name, index))
+            })
+            .collect()
+    }
+
+    // The sequence spelled out by a path
+    pub fn path_sequence(&self, name: &str) -> Result<String, Error> {
+        match self.path(name) {
+            Some(steps) => steps.iter().map(|h| self.sequence(*h)).collect(),
+            None => Err(Error::MissingPath(name.to_owned())),
+        }
+    }
+
+    // Replace every step on the node `id` with `with(step)`
+    fn replace_steps<F: Fn(Handle) -> Vec<Handle>>(&mut self, id: NodeId, with: F) {
+        for steps in self.paths.values_mut() {
+            if steps.iter().any(|h| h.id == id) {
+                *steps = steps
+                    .iter()
+                    .flat_map(|h| if h.id == id { with(*h) } else { vec![*h] })
+                    .collect();
+            }
+        }
+    }
 
-            // Add the id of y to the right nodes list of x
-            //x.adjacent.push(y.id);
-            self.get_node_mut(x).unwrap().nodes_right.push(y);
+    // The error for when either x or y isn't in the graph
+    fn missing_node(&self, x: NodeId, y: NodeId) -> Option<Error> {
+        if !self.has_node(x) {
+            Some(Error::MissingNode(x))
+        } else if !self.has_node(y) {
+            Some(Error::MissingNode(y))
         } else {
-            if !self.has_node(x) && !self.has_node(y) {
-                // Both x and y aren't in the graph
-                panic!("Both nodes {} {} aren't in the graph", x, y)
-            } else if !self.has_node(x) {
-                // x isn't in the graph
-                panic!("Node {} isn't in the graph", x)
-            } else {
-                // y isn't in the graph
-                panic!("Node {} isn't in the graph", y)
+            None
+        }
+    }
+
+    // Does an edge from the forward strand of x to the forward strand of y exist?
+    pub fn edge_exists(&self, x: NodeId, y: NodeId) -> Result<bool, Error> {
+        self.has_edge(Handle::forward(x), Handle::forward(y))
+    }
+
+    // Does an edge from the end of `from` to the start of `to` exist?
+    pub fn has_edge(&self, from: Handle, to: Handle) -> Result<bool, Error> {
+        if let Some(e) = self.missing_node(from.id, to.id) {
+            return Err(e);
+        }
+
+        // Both nodes hold the edge so it's enough to look at one of them
+        let (side, h) = self.side(from, to);
+        Ok(side.contains(&h))
+    }
+
+    // The side of the node of `from` that the edge from `from` to `to` is on
+    // and the handle it holds for it
+    fn side(&self, from: Handle, to: Handle) -> (&EdgeList, Handle) {
+        let n = self.get_node(from.id).unwrap();
+
+        if from.is_reverse() {
+            (&n.nodes_left, to.flip())
+        } else {
+            (&n.nodes_right, to)
+        }
+    }
+
+    // Add an edge from the end of `from` to the start of `to`
+    pub fn add_edge(&mut self, from: Handle, to: Handle) -> Result<(), Error> {
+        if let Some(e) = self.missing_node(from.id, to.id) {
+            return Err(e);
+        }
+
+        // Add `to` to the side of `from` we leave it through
+        let x = self.get_node_mut(from.id).unwrap();
+        if from.is_reverse() {
+            x.nodes_left.push(to.flip());
+        } else {
+            x.nodes_right.push(to);
+        }
+
+        // An edge that is its own reverse e.g. x+ to x- is on a single side
+        if Edge(from, to) == Edge(from, to).flip() {
+            return Ok(());
+        }
+
+        // Add `from` to the side of `to` we enter it through
+        let y = self.get_node_mut(to.id).unwrap();
+        if to.is_reverse() {
+            y.nodes_right.push(from.flip());
+        } else {
+            y.nodes_left.push(from);
+        }
+
+        Ok(())
+    }
+
+    // Remove the edge from the end of `from` to the start of `to` if there's one
+    pub fn remove_edge(&mut self, from: Handle, to: Handle) -> Result<(), Error> {
+        if let Some(e) = self.missing_node(from.id, to.id) {
+            return Err(e);
+        }
+
+        let x = self.get_node_mut(from.id).unwrap();
+        if from.is_reverse() {
+            x.nodes_left.retain(|h| *h != to.flip());
+        } else {
+            x.nodes_right.retain(|h| *h != to);
+        }
+
+        let y = self.get_node_mut(to.id).unwrap();
+        if to.is_reverse() {
+            y.nodes_right.retain(|h| *h != from.flip());
+        } else {
+            y.nodes_left.retain(|h| *h != from);
+        }
+
+        Ok(())
+    }
+
+    // Remove a node and the edges of its neighbours to it
+    pub fn remove_node(&mut self, id: NodeId) -> Result<Node, Error> {
+        let n = match self.take(id) {
+            Some(n) => n,
+            None => return Err(Error::MissingNode(id)),
+        };
+
+        for x in neighbours(&n) {
+            if let Some(x) = self.nodes.get_mut(&x) {
+                x.nodes_left.retain(|h| h.id != id);
+                x.nodes_right.retain(|h| h.id != id);
+            }
+        }
+
+        self.replace_steps(id, |_| Vec::new());
+
+        Ok(n)
+    }
+
+    // Make the reverse strand of a node its forward strand.
+    // The sequence of the node changes and so does its hash but it keeps its id.
+    // Returns the id of the node.
+    pub fn reverse_node(&mut self, id: NodeId) -> Result<NodeId, Error> {
+        let n = match self.take(id) {
+            Some(n) => n,
+            None => return Err(Error::MissingNode(id)),
+        };
+
+        let segment = utils::reverse_complement(&n.segment);
+        let hash = n.hash.map(|_| self.scheme.hash(&segment, n.offset));
+        let mut m = Node::with_hash(
+            hash,
+            segment,
+            n.offset,
+            n.reference.clone(),
+            Vec::new(),
+            Vec::new(),
+        );
+        m.id = id;
+
+        // The forward strand of m is the reverse strand of n
+        let reverse = |h: &Handle| if h.id == id { h.flip() } else { *h };
+
+        // The right side of n is the left side of m and the other way round
+        m.nodes_left = n.nodes_right.iter().map(|h| reverse(&h.flip())).collect();
+        m.nodes_right = n.nodes_left.iter().map(|h| reverse(&h.flip())).collect();
+
+        for x in neighbours(&n) {
+            if let Some(x) = self.nodes.get_mut(&x) {
+                for h in x.nodes_left.iter_mut().chain(x.nodes_right.iter_mut()) {
+                    *h = reverse(h);
+                }
+            }
+        }
+
+        self.insert(m);
+        self.replace_steps(id, |h| vec![reverse(&h)]);
+
+        Ok(id)
+    }
+
+    // Add an edge from the forward strand of x to the forward strand of y
+    pub fn add_edge_from_id(&mut self, x: NodeId, y: NodeId) -> Result<(), Error> {
+        self.add_edge(Handle::forward(x), Handle::forward(y))
+    }
+
+    // The sequence of a node read on the strand of `handle`
+    pub fn sequence(&self, handle: Handle) -> Result<String, Error> {
+        match self.get_node(handle.id) {
+            Some(n) if handle.is_reverse() => Ok(utils::reverse_complement(&n.segment)),
+            Some(n) => Ok(n.segment.clone()),
+            None => Err(Error::MissingNode(handle.id)),
+        }
+    }
+
+    // Every edge in the graph once, in its canonical form
+    pub fn edges(&self) -> Vec<Edge> {
+        let mut seen: HashSet<Edge> = HashSet::new();
+
+        self.nodes
+            .values()
+            .flat_map(|n| n.edges())
+            .map(|e| e.canonical())
+            .filter(|e| seen.insert(*e))
+            .collect()
+    }
+
+    // Split a node in two at `position` on its reference.
+    // The edges of the node are moved to the new left and right nodes.
+    // Returns the ids of the left and right nodes.
+    pub fn split_node(&mut self, id: NodeId, position: usize) -> Result<(NodeId, NodeId), Error> {
+        let n = match self.get_node(id) {
+            Some(n) => n,
+            None => return Err(Error::MissingNode(id)),
+        };
+
+        if position <= n.offset || position >= n.offset + n.segment.len() {
+            return Err(Error::OutOfRange {
+                contig: n.reference().unwrap_or_default().to_owned(),
+                position: position as u64 + 1,
+            });
+        }
+
+        let n = self.take(id).unwrap();
+        let at = position - n.offset;
+        let (left_segment, right_segment) = (&n.segment[..at], &n.segment[at..]);
+        let mut left = Node::with_hash(
+            n.hash.map(|_| self.scheme.hash(left_segment, n.offset)),
+            left_segment.to_owned(),
+            n.offset,
+            n.reference.clone(),
+            Vec::new(),
+            n.nodes_left,
+        );
+        let mut right = Node::with_hash(
+            n.hash.map(|_| self.scheme.hash(right_segment, position)),
+            right_segment.to_owned(),
+            position,
+            n.reference,
+            n.nodes_right,
+            Vec::new(),
+        );
+        left.id = self.fresh_id();
+        right.id = self.fresh_id();
+
+        // A self loop now goes between the left and right nodes
+        replace_edge(&mut left.nodes_left, true, id, (left.id, right.id));
+        replace_edge(&mut right.nodes_right, false, id, (left.id, right.id));
+
+        left.nodes_right.push(Handle::forward(right.id));
+        right.nodes_left.push(Handle::forward(left.id));
+
+        // Point the neighbours at the new nodes
+        let others: HashSet<NodeId> = left
+            .nodes_left
+            .iter()
+            .chain(right.nodes_right.iter())
+            .map(|h| h.id)
+            .filter(|x| *x != left.id && *x != right.id)
+            .collect();
+
+        for x in others {
+            if let Some(x) = self.nodes.get_mut(&x) {
+                replace_edge(&mut x.nodes_left, true, id, (left.id, right.id));
+                replace_edge(&mut x.nodes_right, false, id, (left.id, right.id));
             }
         }
+
+        // Paths visit both nodes, in reverse order on the reverse strand
+        let (l, r) = (Handle::forward(left.id), Handle::forward(right.id));
+        self.replace_steps(id, |h| {
+            if h.is_reverse() {
+                vec![r.flip(), l.flip()]
+            } else {
+                vec![l, r]
+            }
+        });
+
+        let ids = (left.id, right.id);
+        self.insert(left);
+        self.insert(right);
+
+        Ok(ids)
+    }
+}
+
+// The ids of the other nodes that share an edge with `n`
+fn neighbours(n: &Node) -> HashSet<NodeId> {
+    n.nodes_left
+        .iter()
+        .chain(n.nodes_right.iter())
+        .map(|h| h.id)
+        .filter(|x| *x != n.id)
+        .collect()
+}
+
+// Make the handles in `edges` of the node `from` that was split into `to`
+// refer to the left or the right node depending on the side of `from` they reach.
+// A forward handle on a right side enters `from` from its left side and so does
+// a reverse handle on a left side since it's the end of the reverse strand.
+fn replace_edge(edges: &mut EdgeList, is_left_side: bool, from: NodeId, to: (NodeId, NodeId)) {
+    for h in edges.iter_mut().filter(|h| h.id == from) {
+        let id = if h.is_reverse() == is_left_side {
+            to.0
+        } else {
+            to.1
+        };
+
+        *h = Handle::new(id, h.orientation);
     }
 }
 
@@ -200,24 +1088,88 @@ mod tests {
     const RAW_REF: &str = "GHR38";
     const OFFSET: usize = 23;
 
-    fn yield_node<'a>() -> Node<'a> {
-        Node::new(&RAW_SEQ[..], OFFSET, &RAW_REF[..], Vec::new(), Vec::new())
+    fn yield_node() -> Node {
+        Node::new(RAW_SEQ, OFFSET, RAW_REF, Vec::new(), Vec::new())
     }
 
-    fn yeild_id() -> NodeId {
-        NodeId(utils::gen_node_hash(RAW_SEQ, 23).unwrap())
+    fn yeild_hash() -> NodeHash {
+        NodeHash(utils::gen_node_hash(RAW_SEQ, 23).unwrap())
     }
+    #[test]
+    fn test_can_parse_node_id() {
+        let id = NodeId::new(23);
+
+        assert_eq!(id.to_string().parse::<NodeId>().unwrap(), id);
+        assert!("GATTACA".parse::<NodeId>().is_err());
+        assert!("-1".parse::<NodeId>().is_err());
+    }
+
+    #[test]
+    fn test_can_parse_node_hash() {
+        let hash = yeild_hash();
+
+        assert_eq!(hash.to_string().parse::<NodeHash>().unwrap(), hash);
+        assert!("GATTACA".parse::<NodeHash>().is_err());
+        assert!("abcd".parse::<NodeHash>().is_err());
+    }
+
+    #[test]
+    fn test_hash_schemes() {
+        let default = HashScheme::default();
+        assert_eq!(default.hash(RAW_SEQ, OFFSET), yeild_hash());
+        assert_eq!(default.to_string(), "sha256:text");
+
+        for s in ["sha256:binary", "blake3:text", "xxh3:none"].iter() {
+            assert_eq!(s.parse::<HashScheme>().unwrap().to_string(), *s);
+        }
+        assert_eq!(
+            "blake3".parse::<HashScheme>().unwrap().to_string(),
+            "blake3:text"
+        );
+        assert!("md5:text".parse::<HashScheme>().is_err());
+        assert!("sha256:hex".parse::<HashScheme>().is_err());
+
+        // Only the segment goes into content hashes
+        let content = HashScheme::new(HashAlgorithm::Blake3, PositionEncoding::None);
+        assert_eq!(content.hash(RAW_SEQ, 0), content.hash(RAW_SEQ, OFFSET));
+
+        let binary = HashScheme::new(HashAlgorithm::Blake3, PositionEncoding::Binary);
+        assert_ne!(binary.hash(RAW_SEQ, 0), binary.hash(RAW_SEQ, OFFSET));
+        assert_ne!(binary.hash(RAW_SEQ, 0), content.hash(RAW_SEQ, 0));
+    }
+
+    #[test]
+    fn test_graph_hashes_with_its_scheme() {
+        let scheme = HashScheme::new(HashAlgorithm::Xxh3, PositionEncoding::Binary);
+        let mut g = Graph::with_scheme(scheme);
+        let x = g.add_node(yield_node());
+
+        let hash = scheme.hash(RAW_SEQ, OFFSET);
+        assert_eq!(g.get_node(x).unwrap().hash(), Some(hash));
+        assert_eq!(g.id_of_hash(hash), Some(x));
+
+        // Nodes of a graph with another scheme are hashed again
+        let mut other = Graph::new();
+        let y = other.add_node(Node::new("GAT", 0, "chr2", Vec::new(), Vec::new()));
+        other.split_node(y, 1).unwrap();
+
+        g.merge(other);
+        assert!(g.id_of_hash(scheme.hash("G", 0)).is_some());
+        assert!(g.id_of_hash(scheme.hash("AT", 1)).is_some());
+        assert_eq!(g.id_of_hash(NodeHash::new("G", 0)), None);
+    }
+
     // Node
     #[test]
     fn test_can_create_node() {
         let n: Node = yield_node();
-        let id = yeild_id();
         let empty_node_list: EdgeList = Vec::new();
 
         assert_eq!(n.segment, RAW_SEQ);
         assert_eq!(n.offset, 23);
-        assert_eq!(n.id, id);
-        assert_eq!(n.reference, RAW_REF);
+        // The graph hashes the node when it's added
+        assert_eq!(n.hash(), None);
+        assert_eq!(n.reference(), Some(RAW_REF));
         assert_eq!(n.nodes_left, empty_node_list);
         assert_eq!(n.nodes_right, empty_node_list);
     }
@@ -225,15 +1177,167 @@ mod tests {
     // Graph
     #[test]
     fn test_can_create_a_singleton_graph() {
-        let id = yeild_id();
         let n = yield_node();
         let mut g = Graph::new();
-        g.add_node(n);
+        let id = g.add_node(n);
 
+        assert_eq!(id, NodeId::new(1));
         assert!(g.has_node(id));
+        assert_eq!(g.id_of_hash(yeild_hash()), Some(id));
+    }
+
+    #[test]
+    fn test_add_node_gives_fresh_ids() {
+        let mut g = Graph::new();
+        let x = g.add_node(yield_node());
+        let y = g.add_node(Node::alternative("CC", OFFSET, Vec::new(), Vec::new()));
+
+        // The same content gets an id of its own, the hash maps to the first node with it
+        let z = g.add_node(Node::alternative(RAW_SEQ, OFFSET, Vec::new(), Vec::new()));
+        assert_eq!(y, NodeId::new(2));
+        assert_eq!(z, NodeId::new(3));
+        assert_eq!(g.hashmap().len(), 3);
+        assert_eq!(g.id_of_hash(yeild_hash()), Some(x));
+    }
+
+    #[test]
+    fn test_merge_renumbers_nodes() {
+        let mut g = Graph::new();
+        let x = g.add_node(yield_node());
+        g.add_path(RAW_REF).unwrap();
+        g.append_step(RAW_REF, Handle::forward(x)).unwrap();
+
+        let mut other = Graph::new();
+        let y = other.add_node(Node::new("GAT", 0, "chr2", Vec::new(), Vec::new()));
+        let z = other.add_node(Node::alternative("C", 3, Vec::new(), Vec::new()));
+        other.add_edge_from_id(y, z).unwrap();
+        other.add_path("chr2").unwrap();
+        other.append_step("chr2", Handle::forward(y)).unwrap();
+
+        g.merge(other);
+
+        let (y, z) = (NodeId::new(2), NodeId::new(3));
+        assert_eq!(g.hashmap().len(), 3);
+        assert_eq!(g.get_node(y).unwrap().segment, "GAT");
+        assert!(g.edge_exists(y, z).unwrap());
+        assert_eq!(g.path("chr2").unwrap(), &[Handle::forward(y)]);
+        assert_eq!(g.path(RAW_REF).unwrap(), &[Handle::forward(x)]);
+    }
+
+    #[test]
+    fn test_add_edge() {
+        let x = yield_node();
+        let y = Node::alternative("CC", OFFSET + RAW_SEQ.len(), Vec::new(), Vec::new());
+
+        let mut g = add_nodes!(x, y);
+        let (x_id, y_id, z_id) = (NodeId::new(1), NodeId::new(2), NodeId::new(3));
+        g.add_edge_from_id(x_id, y_id).unwrap();
+
+        assert!(g.edge_exists(x_id, y_id).unwrap());
+        assert!(!g.edge_exists(y_id, x_id).unwrap());
+
+        match g.add_edge_from_id(x_id, z_id) {
+            Err(Error::MissingNode(id)) => assert_eq!(id, z_id),
+            _ => panic!("Expected a missing node error"),
+        }
+        assert!(g.edge_exists(z_id, x_id).is_err());
+    }
+
+    #[test]
+    fn test_can_split_node() {
+        let l = Node::new("GAT", 20, RAW_REF, Vec::new(), Vec::new());
+        let n = yield_node();
+        let r = Node::alternative("CC", OFFSET + RAW_SEQ.len(), Vec::new(), Vec::new());
+
+        let mut g = Graph::new();
+        let (l_id, n_id, r_id) = (g.add_node(l), g.add_node(n), g.add_node(r));
+        g.add_edge_from_id(l_id, n_id).unwrap();
+        g.add_edge_from_id(n_id, r_id).unwrap();
+
+        let (x, y) = g.split_node(n_id, OFFSET + 4).unwrap();
+        let x_node = g.get_node(x).unwrap();
+        let y_node = g.get_node(y).unwrap();
+
+        assert!(!g.has_node(n_id));
+        assert_eq!(x_node.segment, &RAW_SEQ[..4]);
+        assert_eq!(x_node.offset(), OFFSET);
+        assert_eq!(y_node.segment, &RAW_SEQ[4..]);
+        assert_eq!(y_node.offset(), OFFSET + 4);
+        assert_eq!(y_node.reference(), Some(RAW_REF));
+
+        assert!(g.edge_exists(l_id, x).unwrap());
+        assert!(g.edge_exists(x, y).unwrap());
+        assert!(g.edge_exists(y, r_id).unwrap());
+        assert!(g.split_node(x, OFFSET).is_err());
+    }
+
+    #[test]
+    fn test_handles() {
+        let id = NodeId::new(1);
+        let h = Handle::forward(id);
+
+        assert!(!h.is_reverse());
+        assert!(h.flip().is_reverse());
+        assert_eq!(h.flip().flip(), h);
+        assert_eq!(h.flip().to_string(), format!("{}-", id));
+
+        let e = Edge(h, Handle::reverse(id));
+        assert_eq!(e.flip(), e);
+    }
+
+    #[test]
+    fn test_can_read_the_reverse_strand() {
+        let n = Node::new("GATTACA", OFFSET, RAW_REF, Vec::new(), Vec::new());
+        let mut g = Graph::new();
+        let id = g.add_node(n);
+
+        assert_eq!(g.sequence(Handle::forward(id)).unwrap(), "GATTACA");
+        assert_eq!(g.sequence(Handle::reverse(id)).unwrap(), "TGTAATC");
+    }
+
+    #[test]
+    fn test_add_edge_between_sides() {
+        let x = yield_node();
+        let y = Node::alternative("CC", OFFSET + RAW_SEQ.len(), Vec::new(), Vec::new());
+
+        let mut g = Graph::new();
+        let (x, y) = (
+            Handle::forward(g.add_node(x)),
+            Handle::forward(g.add_node(y)),
+        );
+        g.add_edge(x, y.flip()).unwrap();
+        g.add_edge(x.flip(), x).unwrap();
+
+        // x+ to y- is y+ to x- read on the other strand
+        assert!(g.has_edge(x, y.flip()).unwrap());
+        assert!(g.has_edge(y, x.flip()).unwrap());
+        assert!(!g.has_edge(x, y).unwrap());
+        assert!(g.has_edge(x.flip(), x).unwrap());
+
+        // The loop on the left side of x is only held once
+        assert_eq!(g.get_node(x.id()).unwrap().nodes_left, vec![x.flip()]);
+        assert_eq!(g.get_node(y.id()).unwrap().nodes_right, vec![x.flip()]);
+        assert_eq!(g.edges().len(), 2);
     }
 
     #[test]
-    #[ignore]
-    fn test_add_edge() {}
+    fn test_can_split_node_with_inversions() {
+        let n = yield_node();
+        let r = Node::alternative("CC", OFFSET + RAW_SEQ.len(), Vec::new(), Vec::new());
+
+        let mut g = Graph::new();
+        let (n_id, r_id) = (g.add_node(n), g.add_node(r));
+        let (n, r) = (Handle::forward(n_id), Handle::forward(r_id));
+        g.add_edge(n, r.flip()).unwrap();
+        g.add_edge(n.flip(), n).unwrap();
+
+        let (x, y) = g.split_node(n_id, OFFSET + 4).unwrap();
+        let (x, y) = (Handle::forward(x), Handle::forward(y));
+
+        assert!(g.has_edge(x, y).unwrap());
+        assert!(g.has_edge(y, r.flip()).unwrap());
+        assert!(g.has_edge(r, y.flip()).unwrap());
+        assert!(g.has_edge(x.flip(), x).unwrap());
+        assert_eq!(g.edges().len(), 3);
+    }
 }
@@ -0,0 +1,715 @@
+//! Augment an existing graph with new variation data
+//!
+//! Each record is located on the reference nodes of its sequence.
+//! The reference nodes are split at the start and end of the reference allele
+//! and a node for each alternative allele is added alongside them.
+//! An empty allele of an indel is an edge around the others.
+//! Inversions and duplications are edges between the reference nodes they span.
+//! Breakends are edges between the reference nodes at their breakpoints,
+//! they wait until the other records are in since they can join any two sequences.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+// Files
+use std::io::BufReader;
+use std::io::Read;
+
+// VCF
+use vcf::{VCFReader, VCFRecord};
+
+use crate::error::Error;
+use crate::graph::types::{Graph, Handle, Node, NodeId, Orientation};
+use crate::io::variation::{self, Alleles, Breakend, Breakpoint, RefCheck, StructuralVariant};
+
+/// Add the variation data in `vcf_reader` to `graph`
+///
+/// Records that are already in the graph are skipped so updating a graph
+/// twice with the same data leaves it unchanged.
+///
+/// Records are checked against the reference nodes of the graph,
+/// `ref_check` says what to do with those that don't match.
+/// Those we don't stop at are returned as warnings.
+pub fn update<R: Read>(
+    graph: &mut Graph,
+    vcf_reader: &mut VCFReader<BufReader<R>>,
+    ref_check: RefCheck,
+) -> Result<Vec<Error>, Error> {
+    let mut index = ReferenceIndex::new(graph);
+    let mut added = AlleleIndex::new(graph);
+    let mut breakends: Vec<VCFRecord> = Vec::new();
+    let mut warnings: Vec<Error> = Vec::new();
+
+    for result_record in vcf_reader.iter() {
+        let record = result_record?;
+        let sequence_id = &record.chromosome[..];
+
+        if variation::has_breakends(&record) {
+            breakends.push(record);
+            continue;
+        }
+
+        // VCF positions are 1 based and point at the first base of the reference allele
+        let record_start = (record.position as usize).saturating_sub(1);
+        let record_end = record_start + record.reference.len();
+
+        // The reference each structural variant spans, None if it isn't all on the sequence
+        let variants = StructuralVariant::new(&record)?;
+        let mut spans: Vec<Option<String>> = Vec::new();
+        for variant in variants.iter() {
+            let (start, end) = variant.span();
+            spans.push(index.sequence(graph, sequence_id, start, end)?);
+        }
+
+        // A structural variant that isn't on the sequence makes the record out of range
+        let found = if spans.iter().all(Option::is_some) {
+            index.sequence(graph, sequence_id, record_start, record_end)?
+        } else {
+            None
+        };
+
+        let checked = variation::check_reference(&record, found.as_ref().map(|s| s.as_bytes()));
+        if !ref_check.keep(checked, &mut warnings)? {
+            continue;
+        }
+
+        // A record whose alleles are all structural variants has no site of its own
+        let alleles = Alleles::new(&record);
+        if variants.is_empty() || !alleles.alternatives.is_empty() {
+            add_variation(graph, &mut index, &mut added, sequence_id, &alleles)?;
+        }
+
+        for (variant, reference) in variants.iter().zip(spans) {
+            match variant.alleles(&reference.unwrap_or_default()) {
+                Some(alleles) => {
+                    add_variation(graph, &mut index, &mut added, sequence_id, &alleles)?
+                }
+                None => add_rearrangement(graph, &mut index, sequence_id, variant)?,
+            }
+        }
+    }
+
+    join_breakends(graph, &breakends, ref_check, &mut warnings)?;
+
+    Ok(warnings)
+}
+
+/// Join the parts of the reference that the breakends in `records` make adjacent
+///
+/// The records are checked against the reference nodes like in [`update`],
+/// those that don't match and that we don't stop at go in `warnings`.
+/// A breakend and its mate, the record named by its `MATEID`, must join the same
+/// breakpoints and only one of them is added.
+/// The reference nodes are split at the breakpoints and an edge joins them,
+/// through a node for the inserted sequence if there's one.
+///
+/// [`update`]: fn.update.html
+pub fn join_breakends(
+    graph: &mut Graph,
+    records: &[VCFRecord],
+    ref_check: RefCheck,
+    warnings: &mut Vec<Error>,
+) -> Result<(), Error> {
+    let mut index = ReferenceIndex::new(graph);
+    let mut added = AlleleIndex::new(graph);
+
+    let mut breakends: Vec<Breakend> = Vec::new();
+    for record in records {
+        let record_start = (record.position as usize).saturating_sub(1);
+        let record_end = record_start + record.reference.len();
+
+        let found = index.sequence(graph, &record.chromosome, record_start, record_end)?;
+        let checked = variation::check_reference(record, found.as_ref().map(|s| s.as_bytes()));
+        if ref_check.keep(checked, warnings)? {
+            breakends.extend(Breakend::new(record)?);
+        }
+    }
+
+    let named: HashMap<&str, &Breakend> = breakends
+        .iter()
+        .filter_map(|breakend| Some((breakend.id.as_deref()?, breakend)))
+        .collect();
+    let mut joined: HashSet<&str> = HashSet::new();
+
+    for breakend in breakends.iter() {
+        let mate = breakend.mate.as_deref().and_then(|id| named.get(id));
+
+        if let Some(mate) = mate {
+            if !breakend.same_adjacency(mate) {
+                return Err(Error::Malformed(format!(
+                    "breakend {} and its mate {} don't join the same breakpoints",
+                    breakend.id.as_deref().unwrap_or("."),
+                    mate.id.as_deref().unwrap_or(".")
+                )));
+            }
+
+            if matches!(mate.id.as_deref(), Some(id) if joined.contains(id)) {
+                continue;
+            }
+        }
+
+        if let Some(id) = breakend.id.as_deref() {
+            joined.insert(id);
+        }
+
+        add_breakend(graph, &mut index, &mut added, breakend)?;
+    }
+
+    Ok(())
+}
+
+// Add the alleles of a VCF record on the sequence `sequence_id` to the graph
+fn add_variation(
+    graph: &mut Graph,
+    index: &mut ReferenceIndex,
+    added: &mut AlleleIndex,
+    sequence_id: &str,
+    alleles: &Alleles,
+) -> Result<(), Error> {
+    // Make the reference allele start and end on node boundaries
+    index.boundary(graph, sequence_id, alleles.start)?;
+    index.boundary(graph, sequence_id, alleles.end())?;
+
+    // The reference nodes that spell out the reference allele
+    let reference_nodes = index.nodes(sequence_id, alleles.start, alleles.end());
+
+    // The handles with an edge into the allele and those with an edge out of it.
+    // An insertion goes in front of the reference node that starts where it does
+    // or after the last node of the sequence.
+    let (tails, heads) = match (reference_nodes.first(), reference_nodes.last()) {
+        (Some(first), Some(last)) => (
+            graph.get_node(*first).unwrap().nodes_left.clone(),
+            graph.get_node(*last).unwrap().nodes_right.clone(),
+        ),
+        _ => match index.node_at(sequence_id, alleles.start) {
+            Some(next) => (
+                graph.get_node(next).unwrap().nodes_left.clone(),
+                vec![Handle::forward(next)],
+            ),
+            None => (
+                index
+                    .node_before(sequence_id, alleles.start)
+                    .into_iter()
+                    .map(Handle::forward)
+                    .collect(),
+                Vec::new(),
+            ),
+        },
+    };
+
+    // Each alternative allele goes alongside the reference nodes
+    for alt in alleles.alternatives.iter().filter(|alt| !alt.is_empty()) {
+        if added.get(sequence_id, alleles.start, alt).is_some() {
+            continue;
+        }
+
+        let n = Node::alternative(alt.clone(), alleles.start, Vec::new(), Vec::new());
+        let id = graph.add_node(n);
+        added.insert(sequence_id, alleles.start, alt, id);
+
+        for tail in tails.iter() {
+            graph.add_edge(*tail, Handle::forward(id))?;
+        }
+
+        for head in heads.iter() {
+            graph.add_edge(Handle::forward(id), *head)?;
+        }
+    }
+
+    // An empty allele is an edge around the others
+    if alleles.has_empty() {
+        for tail in tails.iter() {
+            for head in heads.iter() {
+                if !graph.has_edge(*tail, *head)? {
+                    graph.add_edge(*tail, *head)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Add the edges of an inversion or a duplication between the reference nodes it spans.
+// An inversion goes from the nodes before it to the reverse strand of its last node
+// and from the reverse strand of its first node to the nodes after it.
+// A duplication goes back from its last node to its first node.
+pub(crate) fn add_rearrangement(
+    graph: &mut Graph,
+    index: &mut ReferenceIndex,
+    sequence_id: &str,
+    variant: &StructuralVariant,
+) -> Result<(), Error> {
+    let (start, end) = variant.span();
+    index.boundary(graph, sequence_id, start)?;
+    index.boundary(graph, sequence_id, end)?;
+
+    let nodes = index.nodes(sequence_id, start, end);
+    let (first, last) = match (nodes.first(), nodes.last()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return Ok(()),
+    };
+
+    let edges: Vec<(Handle, Handle)> = match variant {
+        StructuralVariant::Inversion { .. } => {
+            let tails = graph.get_node(first).unwrap().nodes_left.iter();
+            let heads = graph.get_node(last).unwrap().nodes_right.iter();
+
+            tails
+                .map(|tail| (*tail, Handle::reverse(last)))
+                .chain(heads.map(|head| (Handle::reverse(first), *head)))
+                .collect()
+        }
+        StructuralVariant::Duplication { .. } => {
+            vec![(Handle::forward(last), Handle::forward(first))]
+        }
+        _ => Vec::new(),
+    };
+
+    for (from, to) in edges {
+        if !graph.has_edge(from, to)? {
+            graph.add_edge(from, to)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Add the edge of a breakend between the reference nodes at its breakpoints
+fn add_breakend(
+    graph: &mut Graph,
+    index: &mut ReferenceIndex,
+    added: &mut AlleleIndex,
+    breakend: &Breakend,
+) -> Result<(), Error> {
+    // Split the nodes at both breakpoints before looking them up,
+    // the second split could be of the node at the first breakpoint
+    for breakpoint in [&breakend.from, &breakend.to].iter() {
+        index.boundary(graph, &breakpoint.contig, breakpoint.position)?;
+    }
+
+    let mut steps = vec![index.breakpoint(&breakend.from, false)?];
+
+    if !breakend.inserted.is_empty() {
+        let (contig, offset) = (&breakend.from.contig[..], breakend.from.position);
+        let id = match added.get(contig, offset, &breakend.inserted) {
+            Some(id) => id,
+            None => {
+                let n =
+                    Node::alternative(breakend.inserted.clone(), offset, Vec::new(), Vec::new());
+                let id = graph.add_node(n);
+                added.insert(contig, offset, &breakend.inserted, id);
+                id
+            }
+        };
+        steps.push(Handle::forward(id));
+    }
+
+    steps.push(index.breakpoint(&breakend.to, true)?);
+
+    for step in steps.windows(2) {
+        if !graph.has_edge(step[0], step[1])? {
+            graph.add_edge(step[0], step[1])?;
+        }
+    }
+
+    Ok(())
+}
+
+// `position` is 0 based but errors are 1 based like VCF positions
+fn out_of_range(sequence_id: &str, position: usize) -> Error {
+    Error::OutOfRange {
+        contig: sequence_id.to_owned(),
+        position: position as u64 + 1,
+    }
+}
+
+// The reference nodes of each sequence ordered by their offset
+pub(crate) struct ReferenceIndex(HashMap<String, BTreeMap<usize, NodeId>>);
+
+impl ReferenceIndex {
+    pub(crate) fn new(graph: &Graph) -> Self {
+        let mut index: HashMap<String, BTreeMap<usize, NodeId>> = HashMap::new();
+
+        for n in graph.hashmap().values() {
+            if let Some(reference) = n.reference() {
+                index
+                    .entry(reference.to_owned())
+                    .or_default()
+                    .insert(n.offset(), n.id);
+            }
+        }
+
+        ReferenceIndex(index)
+    }
+
+    // Split the reference node that covers `position` so that a node starts there.
+    // Nothing to do if a node already starts at `position` or it's the end of the sequence.
+    fn boundary(
+        &mut self,
+        graph: &mut Graph,
+        sequence_id: &str,
+        position: usize,
+    ) -> Result<(), Error> {
+        let nodes = match self.0.get_mut(sequence_id) {
+            Some(nodes) => nodes,
+            None => return Err(Error::UnknownContig(sequence_id.to_owned())),
+        };
+
+        let (offset, id) = match nodes.range(..=position).next_back() {
+            Some((offset, id)) => (*offset, *id),
+            None => return Err(out_of_range(sequence_id, position)),
+        };
+
+        let end = offset + graph.get_node(id).unwrap().segment.len();
+
+        if position == offset {
+            Ok(())
+        } else if position < end {
+            let (left, right) = graph.split_node(id, position)?;
+            nodes.insert(offset, left);
+            nodes.insert(position, right);
+            Ok(())
+        } else if position == end && nodes.range(position..).next().is_none() {
+            // The end of the sequence
+            Ok(())
+        } else {
+            Err(out_of_range(sequence_id, position))
+        }
+    }
+
+    // The reference between `start` and `end` spelled out by the reference nodes.
+    // None when it isn't all on the sequence.
+    fn sequence(
+        &self,
+        graph: &Graph,
+        sequence_id: &str,
+        start: usize,
+        end: usize,
+    ) -> Result<Option<String>, Error> {
+        let nodes = match self.0.get(sequence_id) {
+            Some(nodes) => nodes,
+            None => return Err(Error::UnknownContig(sequence_id.to_owned())),
+        };
+
+        // The offset of the node that covers `start`
+        let first = match nodes.range(..=start).next_back() {
+            Some((offset, _)) => *offset,
+            None => return Ok(None),
+        };
+
+        let mut sequence = String::new();
+        for (offset, id) in nodes.range(first..) {
+            if *offset != first + sequence.len() || *offset >= end {
+                break;
+            }
+
+            sequence.push_str(&graph.get_node(*id).unwrap().segment);
+        }
+
+        Ok(sequence.get(start - first..end - first).map(String::from))
+    }
+
+    // The reference node that starts at `position`
+    fn node_at(&self, sequence_id: &str, position: usize) -> Option<NodeId> {
+        self.0.get(sequence_id)?.get(&position).cloned()
+    }
+
+    // The last reference node that starts before `position`
+    fn node_before(&self, sequence_id: &str, position: usize) -> Option<NodeId> {
+        let nodes = self.0.get(sequence_id)?;
+        nodes.range(..position).next_back().map(|(_, id)| *id)
+    }
+
+    // The handle of the reference node an adjacency leaves `breakpoint` through
+    // or enters it through if `entering`. The nodes have to be split at the breakpoint.
+    fn breakpoint(&self, breakpoint: &Breakpoint, entering: bool) -> Result<Handle, Error> {
+        let contig = &breakpoint.contig[..];
+        let forward = breakpoint.orientation == Orientation::Forward;
+
+        // A forward breakpoint is entered through the node after it and left through the one
+        // before it, a reverse breakpoint the other way around
+        let id = if forward == entering {
+            self.node_at(contig, breakpoint.position)
+        } else {
+            self.node_before(contig, breakpoint.position)
+        };
+
+        match id {
+            Some(id) => Ok(Handle::new(id, breakpoint.orientation)),
+            None => Err(out_of_range(contig, breakpoint.position)),
+        }
+    }
+
+    // The reference nodes that start within [start, end)
+    fn nodes(&self, sequence_id: &str, start: usize, end: usize) -> Vec<NodeId> {
+        match self.0.get(sequence_id) {
+            Some(nodes) => nodes.range(start..end).map(|(_, id)| *id).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+// The nodes off the reference keyed by the sequence they're on, their offset and their segment.
+// Alleles on different sequences can have the same segment at the same offset.
+struct AlleleIndex(HashMap<(String, usize, String), NodeId>);
+
+impl AlleleIndex {
+    fn new(graph: &Graph) -> Self {
+        let mut index: HashMap<(String, usize, String), NodeId> = HashMap::new();
+
+        for n in graph.hashmap().values().filter(|n| n.reference().is_none()) {
+            if let Some(sequence_id) = sequence_of(graph, n.id) {
+                index
+                    .entry((sequence_id, n.offset(), n.segment.clone()))
+                    .and_modify(|id| *id = (*id).min(n.id))
+                    .or_insert(n.id);
+            }
+        }
+
+        AlleleIndex(index)
+    }
+
+    fn get(&self, sequence_id: &str, offset: usize, segment: &str) -> Option<NodeId> {
+        let key = (sequence_id.to_owned(), offset, segment.to_owned());
+        self.0.get(&key).cloned()
+    }
+
+    fn insert(&mut self, sequence_id: &str, offset: usize, segment: &str, id: NodeId) {
+        let key = (sequence_id.to_owned(), offset, segment.to_owned());
+        self.0.insert(key, id);
+    }
+}
+
+// The sequence of the closest reference node to the node `id`, looking before it first.
+// An allele hangs off the reference nodes of the sequence it's on.
+fn sequence_of(graph: &Graph, id: NodeId) -> Option<String> {
+    let mut seen: HashSet<NodeId> = HashSet::new();
+    let mut queue: VecDeque<NodeId> = VecDeque::new();
+    queue.push_back(id);
+
+    while let Some(id) = queue.pop_front() {
+        if !seen.insert(id) {
+            continue;
+        }
+
+        let n = graph.get_node(id)?;
+        if let Some(reference) = n.reference() {
+            return Some(reference.to_owned());
+        }
+
+        let neighbours = n.nodes_left.iter().chain(n.nodes_right.iter());
+        queue.extend(neighbours.map(|h| h.id()));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::types::{Edge, NodeHash};
+    use crate::io::reference;
+    use std::collections::HashSet;
+    use std::io::Cursor;
+
+    const FASTA: &str = "\
+>chr1
+GATCATCGGTTACACAGCTA
+";
+
+    const VCF_HEADER: &str = "
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+";
+
+    fn yeild_vcf(records: &str) -> Cursor<String> {
+        Cursor::new(format!("{}{}", VCF_HEADER, records))
+    }
+
+    // The node hashes and the edges between them of a graph.
+    // Ids depend on the order nodes were added in so graphs are compared by hash.
+    fn summarize(graph: &Graph) -> (HashSet<NodeHash>, HashSet<(NodeHash, NodeHash)>) {
+        let hash = |id: NodeId| graph.get_node(id).unwrap().hash().unwrap();
+        let hashes = graph.hashmap().keys().map(|id| hash(*id)).collect();
+        let edges = graph
+            .edges()
+            .into_iter()
+            .map(|Edge(x, y)| (hash(x.id()), hash(y.id())))
+            .collect();
+
+        (hashes, edges)
+    }
+
+    #[test]
+    fn test_update_matches_construction() {
+        let first = "chr1	5	rs1	A	G	.	PASS	.\n";
+        let second = "chr1	10	rs2	T	C	.	PASS	.\n";
+
+        let mut graph = reference::construct(Cursor::new(FASTA), yeild_vcf(first)).unwrap();
+        let mut vcf_reader = VCFReader::new(yeild_vcf(second)).unwrap();
+        update(&mut graph, &mut vcf_reader, RefCheck::Error).unwrap();
+
+        let both = format!("{}{}", first, second);
+        let expected = reference::construct(Cursor::new(FASTA), yeild_vcf(&both)).unwrap();
+
+        assert_eq!(graph.hashmap().len(), 7);
+        assert_eq!(summarize(&graph), summarize(&expected));
+
+        // Splitting the reference nodes keeps the reference path
+        assert_eq!(graph.path_sequence("chr1").unwrap(), FASTA[6..].trim_end());
+    }
+
+    #[test]
+    fn test_update_adds_indels() {
+        let records = "\
+chr1	4	del	CAT	C	.	PASS	.
+chr1	10	ins	T	TGG	.	PASS	.
+chr1	13	none	C	.	.	PASS	.
+chr1	19	snv	T	G,TA	.	PASS	.
+chr1	20	end	A	AC	.	PASS	.
+";
+
+        let mut graph = reference::construct(Cursor::new(FASTA), yeild_vcf("")).unwrap();
+        let mut vcf_reader = VCFReader::new(yeild_vcf(records)).unwrap();
+        update(&mut graph, &mut vcf_reader, RefCheck::Error).unwrap();
+
+        let expected = reference::construct(Cursor::new(FASTA), yeild_vcf(records)).unwrap();
+        assert_eq!(summarize(&graph), summarize(&expected));
+
+        // Updating again adds neither nodes nor the edges of the empty alleles
+        let mut vcf_reader = VCFReader::new(yeild_vcf(records)).unwrap();
+        update(&mut graph, &mut vcf_reader, RefCheck::Error).unwrap();
+        assert_eq!(summarize(&graph), summarize(&expected));
+        assert_eq!(graph.edges().len(), expected.edges().len());
+    }
+
+    #[test]
+    fn test_update_adds_structural_variants() {
+        let records = "\
+chr1	4	del	C	<DEL>	.	PASS	END=8
+chr1	10	ins	T	<INS>	.	PASS	SVINSSEQ=GG
+chr1	11	inv	T	<INV>	.	PASS	END=14
+chr1	16	dup	A	<DUP>	.	PASS	SVLEN=2
+";
+
+        let mut graph = reference::construct(Cursor::new(FASTA), yeild_vcf("")).unwrap();
+        let mut vcf_reader = VCFReader::new(yeild_vcf(records)).unwrap();
+        update(&mut graph, &mut vcf_reader, RefCheck::Error).unwrap();
+
+        let expected = reference::construct(Cursor::new(FASTA), yeild_vcf(records)).unwrap();
+        assert_eq!(summarize(&graph), summarize(&expected));
+        assert_eq!(graph.edges().len(), expected.edges().len());
+
+        // A structural variant past the end of the sequence is out of range
+        let past_the_end = "chr1	18	inv	C	<INV>	.	PASS	END=30\n";
+        let mut vcf_reader = VCFReader::new(yeild_vcf(past_the_end)).unwrap();
+        match update(&mut graph, &mut vcf_reader, RefCheck::Error) {
+            Err(Error::OutOfRange { position, .. }) => assert_eq!(position, 18),
+            _ => panic!("Expected an out of range error"),
+        }
+    }
+
+    #[test]
+    fn test_update_joins_breakends() {
+        let records = "\
+chr1	5	bnd_1	A	A]chr1:15]	.	PASS	MATEID=bnd_2
+chr1	8	snv	G	C	.	PASS	.
+chr1	15	bnd_2	C	C]chr1:5]	.	PASS	MATEID=bnd_1
+";
+
+        let mut graph = reference::construct(Cursor::new(FASTA), yeild_vcf("")).unwrap();
+        let mut vcf_reader = VCFReader::new(yeild_vcf(records)).unwrap();
+        update(&mut graph, &mut vcf_reader, RefCheck::Error).unwrap();
+
+        let expected = reference::construct(Cursor::new(FASTA), yeild_vcf(records)).unwrap();
+        assert_eq!(summarize(&graph), summarize(&expected));
+        assert_eq!(graph.edges().len(), expected.edges().len());
+    }
+
+    #[test]
+    fn test_update_is_idempotent() {
+        let records = "chr1	5	rs1	A	G	.	PASS	.\n";
+
+        let mut graph = reference::construct(Cursor::new(FASTA), yeild_vcf(records)).unwrap();
+        let before = summarize(&graph);
+
+        let mut vcf_reader = VCFReader::new(yeild_vcf(records)).unwrap();
+        update(&mut graph, &mut vcf_reader, RefCheck::Error).unwrap();
+
+        assert_eq!(summarize(&graph), before);
+    }
+
+    #[test]
+    fn test_update_keeps_alleles_of_sequences_apart() {
+        // The same allele at the same offset of two sequences
+        let fasta = ">chr1\nGATCATCGGT\n>chr2\nGATCATCGGT\n";
+        let records = yeild_vcf("chr1	5	rs1	A	G	.	PASS	.\n");
+        let mut graph = reference::construct(Cursor::new(fasta), records).unwrap();
+
+        let mut vcf_reader = VCFReader::new(yeild_vcf("chr2	5	rs2	A	G	.	PASS	.\n")).unwrap();
+        update(&mut graph, &mut vcf_reader, RefCheck::Error).unwrap();
+
+        assert_eq!(graph.hashmap().len(), 8);
+        assert_eq!(graph.edges().len(), 8);
+
+        // Each G is between the reference nodes of its own sequence
+        let reference = |h: &Handle| {
+            let n = graph.get_node(h.id()).unwrap();
+            n.reference().unwrap().to_owned()
+        };
+        let mut sequences: Vec<String> = Vec::new();
+        for n in graph.hashmap().values().filter(|n| n.reference().is_none()) {
+            let neighbours = n.nodes_left.iter().chain(n.nodes_right.iter());
+            let neighbours: HashSet<String> = neighbours.map(reference).collect();
+            assert_eq!(neighbours.len(), 1);
+            sequences.extend(neighbours);
+        }
+        sequences.sort();
+        assert_eq!(sequences, vec!["chr1", "chr2"]);
+    }
+
+    #[test]
+    fn test_update_checks_the_reference() {
+        let updated = |records: &str, ref_check: RefCheck| {
+            let mut graph = reference::construct(Cursor::new(FASTA), yeild_vcf("")).unwrap();
+            let mut vcf_reader = VCFReader::new(yeild_vcf(records)).unwrap();
+            update(&mut graph, &mut vcf_reader, ref_check).map(|warnings| (graph, warnings))
+        };
+        let mismatch = "chr1	10	rs2	G	C	.	PASS	.\n";
+        let past_the_end = "chr1	20	rs3	AC	A	.	PASS	.\n";
+
+        match updated(mismatch, RefCheck::Error) {
+            Err(Error::RefMismatch {
+                position, expected, ..
+            }) => assert_eq!((position, expected.as_str()), (10, "T")),
+            _ => panic!("Expected a reference mismatch error"),
+        }
+
+        match updated(past_the_end, RefCheck::Error) {
+            Err(Error::OutOfRange { position, .. }) => assert_eq!(position, 20),
+            _ => panic!("Expected an out of range error"),
+        }
+
+        let records = format!("chr1	5	rs1	A	G	.	PASS	.\n{}{}", mismatch, past_the_end);
+        let (skipped, warnings) = updated(&records, RefCheck::Skip).unwrap();
+        let (kept, _) = updated(&records, RefCheck::Warn).unwrap();
+
+        // Both are skipped and reported
+        assert_eq!(warnings.len(), 2);
+
+        assert_eq!(skipped.hashmap().len(), 4);
+        assert_eq!(kept.hashmap().len(), 7);
+        assert_eq!(kept.path_sequence("chr1").unwrap(), FASTA[6..].trim_end());
+    }
+
+    #[test]
+    fn test_update_fails_on_unknown_sequence() {
+        let mut graph = reference::construct(Cursor::new(FASTA), yeild_vcf("")).unwrap();
+        let mut vcf_reader = VCFReader::new(yeild_vcf("chr2	5	rs1	A	G	.	PASS	.\n")).unwrap();
+
+        match update(&mut graph, &mut vcf_reader, RefCheck::Error) {
+            Err(Error::UnknownContig(chr)) => assert_eq!(chr, "chr2"),
+            _ => panic!("Expected an unknown contig error"),
+        }
+    }
+}
@@ -1,13 +1,26 @@
 use sha2::{Digest, Sha256};
 use std::array::TryFromSliceError;
 use std::convert::TryInto;
+use xxhash_rust::xxh3;
 
 // We want to return store hashes as 32 byte arrays instead of
 // hex strings to be conservative with memory usage
-fn sha256(msg: &[u8]) -> Result<[u8; 32], TryFromSliceError> {
+pub fn sha256(msg: &[u8]) -> Result<[u8; 32], TryFromSliceError> {
     Sha256::digest(msg).as_slice().try_into()
 }
 
+pub fn blake3(msg: &[u8]) -> [u8; 32] {
+    *blake3::hash(msg).as_bytes()
+}
+
+// xxh3 hashes are 128 bits, they take the first 16 bytes and the rest are zero
+pub fn xxh3(msg: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash[..16].copy_from_slice(&xxh3::xxh3_128(msg).to_le_bytes());
+
+    hash
+}
+
 pub fn gen_node_hash(seq: &str, pos: usize) -> Result<[u8; 32], TryFromSliceError> {
     let mut hasher = Sha256::new();
     hasher.input(seq.as_bytes());
@@ -20,6 +33,37 @@ pub fn gen_node_hash(seq: &str, pos: usize) -> Result<[u8; 32], TryFromSliceErro
     hasher.result().as_slice().try_into()
 }
 
+// The sequence read on the other strand.
+// IUPAC ambiguity codes are complemented too and the case is kept.
+pub fn reverse_complement(seq: &str) -> String {
+    seq.chars()
+        .rev()
+        .map(|c| {
+            let complement = match c.to_ascii_uppercase() {
+                'A' => 'T',
+                'T' => 'A',
+                'C' => 'G',
+                'G' => 'C',
+                'R' => 'Y',
+                'Y' => 'R',
+                'K' => 'M',
+                'M' => 'K',
+                'B' => 'V',
+                'V' => 'B',
+                'D' => 'H',
+                'H' => 'D',
+                other => other,
+            };
+
+            if c.is_ascii_lowercase() {
+                complement.to_ascii_lowercase()
+            } else {
+                complement
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -47,4 +91,23 @@ mod tests {
             hex_str
         );
     }
+
+    #[test]
+    fn test_gen_other_hashes() {
+        let txt = "Hello, World!".as_bytes();
+
+        assert_eq!(
+            "288a86a79f20a3d6dccdca7713beaed178798296bdfa7913fa2a62d9727bf8f8",
+            hex::encode(blake3(txt))
+        );
+        assert_eq!(xxh3(txt)[16..], [0u8; 16]);
+        assert_ne!(xxh3(txt), xxh3("Hello, world!".as_bytes()));
+    }
+
+    #[test]
+    fn test_reverse_complement() {
+        assert_eq!(reverse_complement("GATTACA"), "TGTAATC");
+        assert_eq!(reverse_complement("gatN"), "Natc");
+        assert_eq!(reverse_complement(""), "");
+    }
 }
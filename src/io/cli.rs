@@ -1,5 +1,21 @@
-use clap::{App, Arg, SubCommand};
+use clap::{App, Arg, ArgMatches, SubCommand};
 use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+
+use vcf::VCFReader;
+
+use crate::error::Error as FlexError;
+use crate::graph::graph::ConstructOptions;
+use crate::graph::types::Graph;
+use crate::graph::update;
+use crate::graph::{dot, gfa};
+use crate::io::filter::{self, RecordFilter, Region};
+use crate::io::input::{self, Reader};
+use crate::io::reference;
+use crate::io::serialization;
+use crate::io::variation::RefCheck;
 
 // Env vars
 const NAME: &str = env!("CARGO_PKG_NAME");
@@ -7,7 +23,16 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 const DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
 const AUTHORS: &str = env!("CARGO_PKG_AUTHORS");
 
-pub fn start() {
+// Output formats a graph can be written in
+const FORMATS: &[&str] = &["gfa", "dot", "cbor"];
+
+// What to do with records that don't match the reference
+const REF_CHECKS: &[&str] = &["error", "skip", "warn"];
+
+// What to do with genotypes that don't say which allele a haplotype has
+const UNPHASED: &[&str] = &["skip", "split", "reference"];
+
+pub fn start() -> Result<(), Box<dyn Error>> {
     let matches = App::new(NAME)
         .version(VERSION)
         .author(AUTHORS)
@@ -40,15 +65,139 @@ pub fn start() {
                 .author(AUTHORS)
                 .arg(
                     Arg::with_name("REFERENCE")
-                        .help("Sets the reference file to use")
+                        .help(
+                            "Sets the reference file to use, - for stdin. \
+                             A file is read through its .fai index, built if it isn't there",
+                        )
                         .required(true)
                         .index(1),
                 )
                 .arg(
                     Arg::with_name("VCF")
-                        .help("Sets the VCF file to use")
+                        .help("Sets the VCF file to use, - for stdin")
                         .required(true)
                         .index(2),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Sets the file to write the graph to, defaults to stdout")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Sets the format to write the graph in")
+                        .possible_values(FORMATS)
+                        .default_value("gfa")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("hash")
+                        .long("hash")
+                        .value_name("SCHEME")
+                        .help("Sets how nodes are hashed as ALGORITHM:POSITION e.g. blake3:binary")
+                        .long_help(
+                            "Sets how nodes are hashed as ALGORITHM:POSITION.\n\
+                             ALGORITHM is one of sha256, blake3 or xxh3.\n\
+                             POSITION is how the offset of a node goes into its hash: \
+                             text, binary or none for content only hashes.\n\
+                             With none nodes with the same segment share a hash \
+                             so they can't be looked up by it.",
+                        )
+                        .default_value("sha256:text")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("ref-check")
+                        .long("ref-check")
+                        .value_name("MODE")
+                        .help(
+                            "Sets what to do with records that don't match the reference: \
+                             stop with an error, skip them or warn and keep them",
+                        )
+                        .possible_values(REF_CHECKS)
+                        .default_value("error")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("haplotypes")
+                        .long("haplotypes")
+                        .help("Adds a path named SAMPLE#HAPLOTYPE#SEQUENCE for each haplotype"),
+                )
+                .arg(
+                    Arg::with_name("unphased")
+                        .long("unphased")
+                        .value_name("MODE")
+                        .help(
+                            "Sets what to do with --haplotypes at a genotype that isn't phased \
+                             or is missing: leave the haplotype out, split its path there \
+                             or follow the reference",
+                        )
+                        .possible_values(UNPHASED)
+                        .default_value("skip")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("samples")
+                        .long("samples")
+                        .value_name("SAMPLES")
+                        .help("Only adds paths for these comma separated samples with --haplotypes")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("pass")
+                        .long("pass")
+                        .help("Only builds records whose FILTER is PASS into the graph"),
+                )
+                .arg(
+                    Arg::with_name("min-qual")
+                        .long("min-qual")
+                        .value_name("QUAL")
+                        .help("Only builds records with at least this QUAL into the graph")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("min-af")
+                        .long("min-af")
+                        .value_name("FREQUENCY")
+                        .help(
+                            "Only builds records with an alternative allele at least this \
+                             frequent by AF or AC/AN into the graph",
+                        )
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("include")
+                        .long("include")
+                        .value_name("EXPRESSION")
+                        .help(
+                            "Only builds records whose INFO matches EXPRESSION into the graph \
+                             e.g. DP>=10, SVTYPE=DEL or a flag such as SOMATIC",
+                        )
+                        .multiple(true)
+                        .number_of_values(1)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("region")
+                        .long("region")
+                        .value_name("REGION")
+                        .help("Only builds the graph of CONTIG or CONTIG:START-END, 1 based")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("regions-file")
+                        .long("regions-file")
+                        .value_name("BED")
+                        .help("Only builds the graph of the regions in a BED file")
+                        .takes_value(true),
                 ),
         )
         .subcommand(
@@ -64,23 +213,190 @@ pub fn start() {
                 )
                 .arg(
                     Arg::with_name("VCF")
-                        .help("Sets the VCF file to use")
+                        .help("Sets the VCF file to use, - for stdin")
                         .required(true)
                         .index(2),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Sets the file to write the graph to, defaults to stdout")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Sets the format to write the graph in")
+                        .possible_values(FORMATS)
+                        .default_value("gfa")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("ref-check")
+                        .long("ref-check")
+                        .value_name("MODE")
+                        .help(
+                            "Sets what to do with records that don't match the reference: \
+                             stop with an error, skip them or warn and keep them",
+                        )
+                        .possible_values(REF_CHECKS)
+                        .default_value("error")
+                        .takes_value(true),
                 ),
         )
         .get_matches();
 
-    // Gets a value for config if supplied by user, or defaults to "default.conf"
-    let config = matches.value_of("config").unwrap_or("default.conf");
-    println!("Value for config: {}", config);
-
-    // Vary the output based on how many times the user used the "verbose" flag
-    // (i.e. 'myprog -v -v -v' or 'myprog -vvv' vs 'myprog -v'
-    match matches.occurrences_of("v") {
-        0 => println!("No verbose info"),
-        1 => println!("Some verbose info"),
-        2 => println!("Tons of verbose info"),
-        3 | _ => println!("Don't be crazy"),
+    match matches.subcommand() {
+        ("construct", Some(construct_matches)) => construct(construct_matches),
+        ("update", Some(update_matches)) => update(update_matches),
+        _ => {
+            eprintln!("{}", matches.usage());
+            Ok(())
+        }
     }
 }
+
+// Build a graph out of a reference and a VCF and write it out
+fn construct(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    // Both are required so clap guarantees they are present
+    let reference = matches.value_of("REFERENCE").unwrap();
+    let vcf = matches.value_of("VCF").unwrap();
+    let options = ConstructOptions {
+        scheme: matches.value_of("hash").unwrap().parse()?,
+        ref_check: matches.value_of("ref-check").unwrap().parse()?,
+        haplotypes: if matches.is_present("haplotypes") {
+            Some(matches.value_of("unphased").unwrap().parse()?)
+        } else {
+            None
+        },
+        samples: matches
+            .value_of("samples")
+            .map(|samples| samples.split(',').map(String::from).collect()),
+        filter: RecordFilter {
+            pass_only: matches.is_present("pass"),
+            min_qual: number(matches, "min-qual")?,
+            info: values(matches, "include")
+                .map(str::parse)
+                .collect::<Result<_, _>>()?,
+            min_allele_frequency: number(matches, "min-af")?,
+        },
+        regions: regions(matches)?,
+    };
+
+    if reference == "-" && vcf == "-" {
+        return Err("Only one of REFERENCE and VCF can be read from stdin".into());
+    }
+
+    // Read the reference through its index unless it's stdin or can't be indexed
+    let (graph, warnings) = if reference == "-" {
+        reference::construct_with(open_input(reference)?, open_input(vcf)?, &options)?
+    } else {
+        reference::construct_file(reference, open_input(vcf)?, &options)?
+    };
+    warn(&warnings);
+
+    write_graph(
+        &graph,
+        matches.value_of("output"),
+        matches.value_of("format").unwrap(),
+    )
+}
+
+// Report what the library left out or kept despite it not matching
+fn warn(warnings: &[FlexError]) {
+    for warning in warnings {
+        eprintln!("Warning: {}", warning);
+    }
+}
+
+// The value of a numeric option if it's there
+fn number(matches: &ArgMatches, name: &str) -> Result<Option<f64>, Box<dyn Error>> {
+    match matches.value_of(name) {
+        Some(value) => match value.parse() {
+            Ok(number) => Ok(Some(number)),
+            Err(_) => Err(format!("--{} expects a number but got {}", name, value).into()),
+        },
+        None => Ok(None),
+    }
+}
+
+// The values of an option that can be given more than once
+fn values<'a>(matches: &'a ArgMatches, name: &str) -> impl Iterator<Item = &'a str> {
+    matches.values_of(name).into_iter().flatten()
+}
+
+// The regions given with --region and in the --regions-file
+fn regions(matches: &ArgMatches) -> Result<Vec<Region>, Box<dyn Error>> {
+    let mut regions: Vec<Region> = values(matches, "region")
+        .map(str::parse)
+        .collect::<Result<_, _>>()?;
+
+    if let Some(bed) = matches.value_of("regions-file") {
+        regions.extend(filter::read_bed(open_input(bed)?)?);
+    }
+
+    Ok(regions)
+}
+
+// Add variation data to an existing graph and write it out
+fn update(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    // Both are required so clap guarantees they are present
+    let graph = matches.value_of("GRAPH").unwrap();
+    let vcf = matches.value_of("VCF").unwrap();
+    let ref_check: RefCheck = matches.value_of("ref-check").unwrap().parse()?;
+
+    let mut graph = read_graph(graph)?;
+    let mut vcf_reader = VCFReader::new(open_input(vcf)?).map_err(FlexError::from)?;
+    let warnings = update::update(&mut graph, &mut vcf_reader, ref_check)?;
+    warn(&warnings);
+
+    write_graph(
+        &graph,
+        matches.value_of("output"),
+        matches.value_of("format").unwrap(),
+    )
+}
+
+// Read a graph that was previously written out
+fn read_graph(fp: &str) -> Result<Graph, Box<dyn Error>> {
+    if fp.ends_with(".gfa") {
+        Ok(gfa::parse_gfa(BufReader::new(open(fp)?))?.graph)
+    } else if fp.ends_with(".cbor") {
+        Ok(serialization::deserialize_graph(BufReader::new(open(fp)?))?)
+    } else {
+        Err(format!("Couldn't read {}: expected a .gfa or .cbor file", fp).into())
+    }
+}
+
+// Open a file for reading and name it in the error when we can't
+fn open(fp: &str) -> Result<File, Box<dyn Error>> {
+    File::open(fp).map_err(|e| format!("Couldn't open {}: {}", fp, e).into())
+}
+
+// Open an input file, or stdin when it's -, that may be gzip or BGZF compressed
+fn open_input(fp: &str) -> Result<Reader, Box<dyn Error>> {
+    input::open(fp).map_err(|e| format!("Couldn't open {}: {}", fp, e).into())
+}
+
+// Write the graph to `output` or stdout when there's no output file
+fn write_graph(graph: &Graph, output: Option<&str>, format: &str) -> Result<(), Box<dyn Error>> {
+    let mut out: Box<dyn Write> = match output {
+        Some(fp) => Box::new(BufWriter::new(File::create(fp)?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+
+    match format {
+        "gfa" => gfa::fmt_graph_to_gfa(graph, &mut out)?,
+        "dot" => dot::fmt_graph_to_dot(graph, &mut out)?,
+        "cbor" => serialization::serialize_graph(&mut out, graph)?,
+        _ => return Err(format!("Unknown output format {}", format).into()),
+    }
+
+    out.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,607 @@
+//! Random access to the sequences of a FASTA file through its index
+//!
+//! A `.fai` index, as `samtools faidx` writes it, has a line per sequence with
+//! the name and length of the sequence, the offset its bases start at in the file,
+//! the bases on each of its lines and the bytes each line takes with its line break.
+//! That's enough to find any base of a FASTA file whose sequences have lines of one length.
+//!
+//! A BGZF compressed FASTA also needs a `.gzi` index of where its blocks start
+//! in the compressed file and in the decompressed data
+//! so we can start decompressing at the block a base is in.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use flate2::bufread::MultiGzDecoder;
+
+use crate::error::Error;
+use crate::io::input::Input;
+
+/// The line of a sequence in a `.fai` index
+#[derive(Debug, PartialEq, Clone)]
+pub struct FaiEntry {
+    pub name: String,
+    pub length: usize,
+    // Where the first base is in the (decompressed) file
+    pub offset: u64,
+    pub line_bases: usize,
+    pub line_width: usize,
+}
+
+impl FaiEntry {
+    // Where base `position` of the sequence is in the (decompressed) file
+    fn offset_of(&self, position: usize) -> u64 {
+        let line = (position / self.line_bases) as u64;
+        let column = (position % self.line_bases) as u64;
+        self.offset + line * self.line_width as u64 + column
+    }
+}
+
+/// Read a `.fai` index
+pub fn read_fai<R: Read>(fai: R) -> Result<Vec<FaiEntry>, Error> {
+    let mut entries: Vec<FaiEntry> = Vec::new();
+
+    for (index, line) in BufReader::new(fai).lines().enumerate() {
+        let line = line?;
+        let line_number = index + 1;
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let malformed = |msg: &str| Error::Malformed(format!("line {}: {}", line_number, msg));
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 5 {
+            return Err(malformed(
+                "expected a name, length, offset, line bases and line width",
+            ));
+        }
+
+        let number = |field: &str| {
+            field
+                .parse::<usize>()
+                .map_err(|_| malformed(&format!("bad number {}", field)))
+        };
+
+        let entry = FaiEntry {
+            name: fields[0].to_owned(),
+            length: number(fields[1])?,
+            offset: number(fields[2])? as u64,
+            line_bases: number(fields[3])?,
+            line_width: number(fields[4])?,
+        };
+
+        if entry.line_bases == 0 || entry.line_width < entry.line_bases {
+            return Err(malformed("bad line length"));
+        }
+
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Write a `.fai` index
+pub fn write_fai<W: Write>(entries: &[FaiEntry], out: &mut W) -> Result<(), Error> {
+    for entry in entries {
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}",
+            entry.name, entry.length, entry.offset, entry.line_bases, entry.line_width
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Index a FASTA file, decompressed if it's compressed.
+///
+/// Every line of a sequence but the last has to have the same number of bases.
+pub fn build_fai<R: Read>(fasta: R) -> Result<Vec<FaiEntry>, Error> {
+    let mut reader = BufReader::new(fasta);
+    let mut entries: Vec<FaiEntry> = Vec::new();
+    let mut names: HashSet<String> = HashSet::new();
+
+    // Where we are in the file and whether the last line of the current sequence was short
+    let mut offset: u64 = 0;
+    let mut short = false;
+
+    let mut line: Vec<u8> = Vec::new();
+    let mut line_number = 0;
+    loop {
+        line.clear();
+        let width = reader.read_until(b'\n', &mut line)?;
+        if width == 0 {
+            break;
+        }
+        line_number += 1;
+        offset += width as u64;
+
+        let malformed = |msg: String| Error::Malformed(format!("line {}: {}", line_number, msg));
+
+        if line.starts_with(b">") {
+            let header = String::from_utf8_lossy(&line[1..]);
+            let name = header.split_whitespace().next().unwrap_or("").to_owned();
+
+            if !names.insert(name.clone()) {
+                return Err(malformed(format!(
+                    "sequence {} is already in the file",
+                    name
+                )));
+            }
+
+            entries.push(FaiEntry {
+                name,
+                length: 0,
+                offset,
+                line_bases: 0,
+                line_width: 0,
+            });
+            short = false;
+            continue;
+        }
+
+        let bases = line.iter().filter(|b| **b != b'\n' && **b != b'\r').count();
+
+        let entry = match entries.last_mut() {
+            Some(entry) => entry,
+            None if bases == 0 => continue,
+            None => return Err(malformed(String::from("expected a FASTA header"))),
+        };
+
+        if bases == 0 {
+            short = true;
+            continue;
+        }
+
+        if short {
+            return Err(malformed(format!(
+                "sequence {} has lines of different lengths",
+                entry.name
+            )));
+        }
+
+        if entry.line_bases == 0 {
+            entry.line_bases = bases;
+            entry.line_width = width;
+        } else if bases > entry.line_bases {
+            return Err(malformed(format!(
+                "sequence {} has lines of different lengths",
+                entry.name
+            )));
+        } else if bases < entry.line_bases || width != entry.line_width {
+            // Only the last line can be different
+            short = true;
+        }
+
+        entry.length += bases;
+    }
+
+    Ok(entries)
+}
+
+/// Read a `.gzi` index, the compressed and decompressed offset of each block
+/// of a BGZF file other than the first one.
+pub fn read_gzi<R: Read>(mut gzi: R) -> Result<Vec<(u64, u64)>, Error> {
+    let mut number = [0u8; 8];
+    let mut read_number = |gzi: &mut R| -> Result<u64, Error> {
+        gzi.read_exact(&mut number)?;
+        Ok(u64::from_le_bytes(number))
+    };
+
+    let count = read_number(&mut gzi)?;
+    let mut blocks: Vec<(u64, u64)> = Vec::new();
+    for _ in 0..count {
+        blocks.push((read_number(&mut gzi)?, read_number(&mut gzi)?));
+    }
+
+    Ok(blocks)
+}
+
+/// Write a `.gzi` index
+pub fn write_gzi<W: Write>(blocks: &[(u64, u64)], out: &mut W) -> Result<(), Error> {
+    out.write_all(&(blocks.len() as u64).to_le_bytes())?;
+    for (compressed, decompressed) in blocks {
+        out.write_all(&compressed.to_le_bytes())?;
+        out.write_all(&decompressed.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Index the blocks of a BGZF file.
+///
+/// The header of each block has the size of the block in it and
+/// the end of the block the size of its data, so nothing needs decompressing.
+/// Gzip that isn't BGZF can't be indexed.
+pub fn build_gzi<R: Read>(mut bgzf: R) -> Result<Vec<(u64, u64)>, Error> {
+    let not_bgzf = || {
+        Error::Unsupported(String::from(
+            "gzip that isn't BGZF can't be indexed, compress it with bgzip",
+        ))
+    };
+
+    let mut blocks: Vec<(u64, u64)> = Vec::new();
+    let (mut compressed, mut decompressed) = (0u64, 0u64);
+
+    loop {
+        // The fixed part of a gzip header up to the length of the extra field
+        let mut header = [0u8; 12];
+        if bgzf.read(&mut header[..1])? == 0 {
+            break;
+        }
+        bgzf.read_exact(&mut header[1..])?;
+
+        // Gzip with an extra field
+        if header[..3] != [0x1f, 0x8b, 8] || header[3] & 4 == 0 {
+            return Err(not_bgzf());
+        }
+
+        let extra_length = u16::from_le_bytes([header[10], header[11]]) as usize;
+        let mut extra = vec![0u8; extra_length];
+        bgzf.read_exact(&mut extra)?;
+
+        // The BC subfield has the size of the block less one
+        let mut size = None;
+        let mut fields = &extra[..];
+        while fields.len() >= 4 {
+            let length = u16::from_le_bytes([fields[2], fields[3]]) as usize;
+            if fields[..2] == *b"BC" && length == 2 && fields.len() >= 6 {
+                size = Some(u16::from_le_bytes([fields[4], fields[5]]) as usize + 1);
+            }
+            fields = fields.get(4 + length..).unwrap_or_default();
+        }
+
+        let size = size.ok_or_else(not_bgzf)?;
+        let rest = size
+            .checked_sub(header.len() + extra_length)
+            .filter(|rest| *rest >= 8)
+            .ok_or_else(not_bgzf)?;
+
+        // The compressed data and the CRC before the size of the decompressed data
+        io::copy(&mut (&mut bgzf).take(rest as u64 - 4), &mut io::sink())?;
+        let mut data_size = [0u8; 4];
+        bgzf.read_exact(&mut data_size)?;
+
+        if compressed > 0 {
+            blocks.push((compressed, decompressed));
+        }
+        compressed += size as u64;
+        decompressed += u32::from_le_bytes(data_size) as u64;
+    }
+
+    Ok(blocks)
+}
+
+/// A FASTA file we can read any part of any sequence of, compressed with BGZF or not
+pub struct IndexedFasta<R: Read + Seek> {
+    reader: R,
+    entries: Vec<FaiEntry>,
+    // The blocks of a BGZF file, None when it isn't compressed
+    blocks: Option<Vec<(u64, u64)>>,
+    // Why the indexes `open` built couldn't be written next to the file
+    warnings: Vec<Error>,
+}
+
+impl<R: Read + Seek> IndexedFasta<R> {
+    /// A FASTA file with the indexes we already have of it
+    pub fn with_index(reader: R, entries: Vec<FaiEntry>, blocks: Option<Vec<(u64, u64)>>) -> Self {
+        IndexedFasta {
+            reader,
+            entries,
+            blocks,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Index a FASTA file by reading all of it once
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let blocks = if is_compressed(&mut reader)? {
+            Some(build_gzi(&mut reader)?)
+        } else {
+            None
+        };
+
+        reader.seek(SeekFrom::Start(0))?;
+        let entries = build_fai(Input::new(&mut reader)?)?;
+
+        Ok(IndexedFasta::with_index(reader, entries, blocks))
+    }
+
+    /// Take why the indexes `open` built couldn't be written next to the file.
+    /// They're only kept in memory then.
+    pub fn take_warnings(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// The sequences in the order they are in the file
+    pub fn sequences(&self) -> &[FaiEntry] {
+        &self.entries
+    }
+
+    pub fn length(&self, name: &str) -> Option<usize> {
+        self.entry(name).map(|entry| entry.length)
+    }
+
+    fn entry(&self, name: &str) -> Option<&FaiEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// The bases of a sequence in `range`, 0 based with the end left out.
+    /// A range that runs past the end of the sequence stops at it.
+    pub fn fetch(&mut self, name: &str, range: Range<usize>) -> Result<Vec<u8>, Error> {
+        let entry = match self.entry(name) {
+            Some(entry) => entry.clone(),
+            None => return Err(Error::UnknownContig(name.to_owned())),
+        };
+
+        let end = range.end.min(entry.length);
+        if range.start > end {
+            return Err(Error::OutOfRange {
+                contig: name.to_owned(),
+                position: range.start as u64 + 1,
+            });
+        }
+        if range.start == end {
+            return Ok(Vec::new());
+        }
+
+        let start = entry.offset_of(range.start);
+        let bytes = entry.offset_of(end - 1) + 1 - start;
+        let mut data: Vec<u8> = Vec::with_capacity(bytes as usize);
+
+        match &self.blocks {
+            None => {
+                self.reader.seek(SeekFrom::Start(start))?;
+                (&mut self.reader).take(bytes).read_to_end(&mut data)?;
+            }
+            Some(blocks) => {
+                // Decompress from the start of the block that the first base is in
+                let (compressed, decompressed) = blocks
+                    .iter()
+                    .take_while(|(_, decompressed)| *decompressed <= start)
+                    .last()
+                    .cloned()
+                    .unwrap_or((0, 0));
+
+                self.reader.seek(SeekFrom::Start(compressed))?;
+                let mut decoder = MultiGzDecoder::new(BufReader::new(&mut self.reader));
+                io::copy(
+                    &mut (&mut decoder).take(start - decompressed),
+                    &mut io::sink(),
+                )?;
+                decoder.take(bytes).read_to_end(&mut data)?;
+            }
+        }
+
+        if data.len() as u64 != bytes {
+            return Err(Error::Malformed(format!(
+                "{} is shorter than its index says",
+                name
+            )));
+        }
+
+        data.retain(|b| *b != b'\n' && *b != b'\r');
+
+        Ok(data)
+    }
+}
+
+impl IndexedFasta<File> {
+    /// Open the FASTA file at `path` with its `.fai` index and, if it's compressed,
+    /// its `.gzi` index next to it. Indexes that aren't there are built and written next to it
+    /// or only kept in memory when we can't write them, see `take_warnings`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let mut reader = File::open(path)?;
+        let mut warnings: Vec<Error> = Vec::new();
+
+        let index_path = |extension: &str| {
+            let mut index_path = path.as_os_str().to_owned();
+            index_path.push(extension);
+            index_path
+        };
+
+        let blocks = if is_compressed(&mut reader)? {
+            let gzi = index_path(".gzi");
+            if Path::new(&gzi).exists() {
+                Some(read_gzi(BufReader::new(File::open(&gzi)?))?)
+            } else {
+                let blocks = build_gzi(BufReader::new(&mut reader))?;
+                warnings.extend(keep_index(&gzi, |out| write_gzi(&blocks, out)).err());
+                Some(blocks)
+            }
+        } else {
+            None
+        };
+
+        let fai = index_path(".fai");
+        let entries = if Path::new(&fai).exists() {
+            read_fai(File::open(&fai)?)?
+        } else {
+            reader.seek(SeekFrom::Start(0))?;
+            let entries = build_fai(Input::new(&mut reader)?)?;
+            warnings.extend(keep_index(&fai, |out| write_fai(&entries, out)).err());
+            entries
+        };
+
+        let mut indexed = IndexedFasta::with_index(reader, entries, blocks);
+        indexed.warnings = warnings;
+
+        Ok(indexed)
+    }
+}
+
+// Whether a file is gzip compressed, leaving it at the start
+fn is_compressed<R: Read + Seek>(reader: &mut R) -> Result<bool, Error> {
+    reader.seek(SeekFrom::Start(0))?;
+    let mut magic: Vec<u8> = Vec::new();
+    reader.take(2).read_to_end(&mut magic)?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    Ok(magic == [0x1f, 0x8b])
+}
+
+// Write an index we built next to the file, it's only a warning when we can't
+fn keep_index<P: AsRef<Path>, F>(path: P, write: F) -> Result<(), Error>
+where
+    F: FnOnce(&mut io::BufWriter<File>) -> Result<(), Error>,
+{
+    let path = path.as_ref();
+    let written = File::create(path).map_err(Error::from).and_then(|f| {
+        let mut out = io::BufWriter::new(f);
+        write(&mut out)?;
+        out.flush()?;
+        Ok(())
+    });
+
+    written.map_err(|e| {
+        Error::Io(io::Error::other(format!(
+            "Couldn't write the index {}: {}",
+            path.display(),
+            e
+        )))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::{Compression, Crc};
+    use std::io::Cursor;
+
+    const FASTA: &str = "\
+>chr1 first sequence
+GATCATCGGT
+TACACAGCTA
+AGT
+>chr2
+TCTTGTTCTC
+AAGA
+";
+
+    // Compress `data` as BGZF with at most `block_size` bytes in a block
+    fn bgzf(data: &[u8], block_size: usize) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+
+        // The end of a BGZF file is an empty block
+        let chunks = data.chunks(block_size).chain(std::iter::once(&[][..]));
+        for chunk in chunks {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(chunk).unwrap();
+            let compressed = encoder.finish().unwrap();
+            let mut crc = Crc::new();
+            crc.update(chunk);
+
+            let size = (18 + compressed.len() + 8 - 1) as u16;
+            out.extend(&[
+                0x1f, 0x8b, 8, 4, 0, 0, 0, 0, 0, 0xff, 6, 0, b'B', b'C', 2, 0,
+            ]);
+            out.extend(&size.to_le_bytes());
+            out.extend(&compressed);
+            out.extend(&crc.sum().to_le_bytes());
+            out.extend(&(chunk.len() as u32).to_le_bytes());
+        }
+
+        out
+    }
+
+    #[test]
+    fn test_builds_and_reads_the_fai_index() {
+        let entries = build_fai(Cursor::new(FASTA)).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                FaiEntry {
+                    name: String::from("chr1"),
+                    length: 23,
+                    offset: 21,
+                    line_bases: 10,
+                    line_width: 11,
+                },
+                FaiEntry {
+                    name: String::from("chr2"),
+                    length: 14,
+                    offset: 53,
+                    line_bases: 10,
+                    line_width: 11,
+                },
+            ]
+        );
+
+        let mut fai: Vec<u8> = Vec::new();
+        write_fai(&entries, &mut fai).unwrap();
+        assert_eq!(
+            String::from_utf8(fai.clone()).unwrap(),
+            "chr1\t23\t21\t10\t11\nchr2\t14\t53\t10\t11\n"
+        );
+        assert_eq!(read_fai(Cursor::new(fai)).unwrap(), entries);
+
+        for bad in &[">chr1\nGAT\nGATC\n", ">chr1\nGA\n>chr1\nGA\n", "GATC\n"] {
+            match build_fai(Cursor::new(bad)) {
+                Err(Error::Malformed(_)) => (),
+                _ => panic!("Expected a malformed input error for {}", bad),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fetches_parts_of_sequences() {
+        let mut fasta = IndexedFasta::new(Cursor::new(FASTA)).unwrap();
+
+        assert_eq!(fasta.length("chr2"), Some(14));
+        assert_eq!(
+            fasta.fetch("chr1", 0..23).unwrap(),
+            b"GATCATCGGTTACACAGCTAAGT"
+        );
+        assert_eq!(fasta.fetch("chr1", 8..12).unwrap(), b"GTTA");
+        assert_eq!(fasta.fetch("chr2", 12..100).unwrap(), b"GA");
+        assert!(fasta.fetch("chr2", 14..20).unwrap().is_empty());
+
+        match fasta.fetch("chr3", 0..1) {
+            Err(Error::UnknownContig(contig)) => assert_eq!(contig, "chr3"),
+            _ => panic!("Expected an unknown contig error"),
+        }
+        match fasta.fetch("chr2", 15..20) {
+            Err(Error::OutOfRange { contig, position }) => {
+                assert_eq!((contig.as_str(), position), ("chr2", 16))
+            }
+            _ => panic!("Expected an out of range error"),
+        }
+    }
+
+    #[test]
+    fn test_fetches_parts_of_bgzf_sequences() {
+        let compressed = bgzf(FASTA.as_bytes(), 16);
+
+        // A block starts every 16 bytes of the FASTA and an empty one ends it
+        let blocks = build_gzi(Cursor::new(&compressed)).unwrap();
+        let starts: Vec<u64> = blocks.iter().map(|(_, start)| *start).collect();
+        assert_eq!(starts, vec![16, 32, 48, 64, 69]);
+
+        let mut gzi: Vec<u8> = Vec::new();
+        write_gzi(&blocks, &mut gzi).unwrap();
+        assert_eq!(read_gzi(Cursor::new(gzi)).unwrap(), blocks);
+
+        let mut fasta = IndexedFasta::new(Cursor::new(compressed)).unwrap();
+        assert_eq!(
+            fasta.sequences(),
+            &build_fai(Cursor::new(FASTA)).unwrap()[..]
+        );
+        assert_eq!(fasta.fetch("chr1", 8..22).unwrap(), b"GTTACACAGCTAAG");
+        assert_eq!(fasta.fetch("chr2", 3..14).unwrap(), b"TGTTCTCAAGA");
+
+        // Plain gzip has no blocks to index
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(FASTA.as_bytes()).unwrap();
+        match IndexedFasta::new(Cursor::new(encoder.finish().unwrap())) {
+            Err(Error::Unsupported(_)) => (),
+            _ => panic!("Expected an unsupported input error"),
+        }
+    }
+}
@@ -0,0 +1,450 @@
+//! Choosing the records and regions a graph is built out of
+
+use std::io::{BufRead, BufReader, Read};
+
+use vcf::VCFRecord;
+
+use crate::error::Error;
+
+/// A part of a sequence, 0 based with `end` past its last base
+/// or `None` when it runs to the end of the sequence.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Region {
+    pub contig: String,
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl Region {
+    /// Whether `start..end` on `contig` is all in the region
+    pub fn contains(&self, contig: &str, start: usize, end: usize) -> bool {
+        self.contig == contig && self.start <= start && end <= self.end.unwrap_or(usize::MAX)
+    }
+}
+
+/// A region written as `contig`, `contig:start` or `contig:start-end`
+/// with 1 based positions and `end` included like `samtools` takes them.
+impl std::str::FromStr for Region {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || Error::Malformed(format!("bad region {}", s));
+        let position = |p: &str| p.replace(',', "").parse::<usize>().map_err(|_| malformed());
+
+        // Contig names can have a colon in them so only the last one is the range
+        let (contig, range) = match s.rfind(':') {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None => (s, ""),
+        };
+
+        if contig.is_empty() {
+            return Err(malformed());
+        }
+
+        if range.is_empty() {
+            return Ok(Region {
+                contig: contig.to_owned(),
+                start: 0,
+                end: None,
+            });
+        }
+
+        let (start, end) = match range.find('-') {
+            Some(i) => (position(&range[..i])?, &range[i + 1..]),
+            None => (position(range)?, ""),
+        };
+        let end = match end {
+            "" => None,
+            end => Some(position(end)?),
+        };
+
+        if start == 0 || matches!(end, Some(e) if e < start) {
+            return Err(malformed());
+        }
+
+        Ok(Region {
+            contig: contig.to_owned(),
+            start: start - 1,
+            end,
+        })
+    }
+}
+
+/// Read the regions in a BED file.
+/// BED positions are 0 based with the end left out so they are the same as those of a `Region`.
+pub fn read_bed<R: Read>(bed: R) -> Result<Vec<Region>, Error> {
+    let mut regions: Vec<Region> = Vec::new();
+
+    for (index, line) in BufReader::new(bed).lines().enumerate() {
+        let line = line?;
+        let line_number = index + 1;
+
+        if line.trim().is_empty()
+            || line.starts_with('#')
+            || line.starts_with("track")
+            || line.starts_with("browser")
+        {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 3 {
+            return Err(Error::Malformed(format!(
+                "line {}: expected a contig, a start and an end",
+                line_number
+            )));
+        }
+
+        let position = |field: &str| {
+            field.parse::<usize>().map_err(|_| {
+                Error::Malformed(format!("line {}: bad position {}", line_number, field))
+            })
+        };
+        let (start, end) = (position(fields[1])?, position(fields[2])?);
+
+        if end < start {
+            return Err(Error::Malformed(format!(
+                "line {}: the region ends before it starts",
+                line_number
+            )));
+        }
+
+        regions.push(Region {
+            contig: fields[0].to_owned(),
+            start,
+            end: Some(end),
+        });
+    }
+
+    Ok(regions)
+}
+
+/// The regions sorted by contig and start with the ones that overlap or touch made into one
+pub fn merge_regions(mut regions: Vec<Region>) -> Vec<Region> {
+    regions.sort_by(|a, b| (&a.contig, a.start).cmp(&(&b.contig, b.start)));
+
+    let mut merged: Vec<Region> = Vec::new();
+    for region in regions {
+        match merged.last_mut() {
+            Some(last)
+                if last.contig == region.contig
+                    && region.start <= last.end.unwrap_or(usize::MAX) =>
+            {
+                last.end = match (last.end, region.end) {
+                    (Some(a), Some(b)) => Some(a.max(b)),
+                    _ => None,
+                };
+            }
+            _ => merged.push(region),
+        }
+    }
+
+    merged
+}
+
+/// A comparison with the values of an INFO field such as `DP>=10` or `SVTYPE=DEL`.
+/// A key by itself such as `SOMATIC` is a flag that has to be there.
+#[derive(Debug, PartialEq, Clone)]
+pub struct InfoExpression {
+    pub key: String,
+    pub comparison: Option<(Comparison, String)>,
+}
+
+/// How an `InfoExpression` compares the values of a field with its value
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl InfoExpression {
+    /// Whether any value of the field in `record` passes the comparison.
+    /// A record without the field never does.
+    /// Values are compared as numbers when both sides are numbers and as text otherwise,
+    /// text is only ever equal or not equal.
+    pub fn matches(&self, record: &VCFRecord) -> bool {
+        let values = match record.info.get(&self.key) {
+            Some(values) => values,
+            None => return false,
+        };
+
+        let (comparison, expected) = match &self.comparison {
+            Some(comparison) => comparison,
+            None => return true,
+        };
+
+        values.iter().any(
+            |value| match (value.parse::<f64>(), expected.parse::<f64>()) {
+                (Ok(value), Ok(expected)) => match comparison {
+                    Comparison::Equal => value == expected,
+                    Comparison::NotEqual => value != expected,
+                    Comparison::Less => value < expected,
+                    Comparison::LessOrEqual => value <= expected,
+                    Comparison::Greater => value > expected,
+                    Comparison::GreaterOrEqual => value >= expected,
+                },
+                _ => match comparison {
+                    Comparison::Equal => value == expected,
+                    Comparison::NotEqual => value != expected,
+                    _ => false,
+                },
+            },
+        )
+    }
+}
+
+impl std::str::FromStr for InfoExpression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || Error::Malformed(format!("bad INFO expression {}", s));
+
+        let i = match s.find(&['!', '<', '>', '='][..]) {
+            Some(i) => i,
+            None if !s.is_empty() => {
+                return Ok(InfoExpression {
+                    key: s.to_owned(),
+                    comparison: None,
+                })
+            }
+            None => return Err(malformed()),
+        };
+
+        let (key, rest) = s.split_at(i);
+        let (comparison, value) = if let Some(value) = rest.strip_prefix("!=") {
+            (Comparison::NotEqual, value)
+        } else if let Some(value) = rest.strip_prefix("<=") {
+            (Comparison::LessOrEqual, value)
+        } else if let Some(value) = rest.strip_prefix(">=") {
+            (Comparison::GreaterOrEqual, value)
+        } else if let Some(value) = rest.strip_prefix('=') {
+            (Comparison::Equal, value)
+        } else if let Some(value) = rest.strip_prefix('<') {
+            (Comparison::Less, value)
+        } else if let Some(value) = rest.strip_prefix('>') {
+            (Comparison::Greater, value)
+        } else {
+            return Err(malformed());
+        };
+
+        if key.is_empty() || value.is_empty() {
+            return Err(malformed());
+        }
+
+        Ok(InfoExpression {
+            key: key.to_owned(),
+            comparison: Some((comparison, value.to_owned())),
+        })
+    }
+}
+
+/// Which records to build a graph out of, all of them by default
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RecordFilter {
+    // Only records whose FILTER is PASS
+    pub pass_only: bool,
+
+    // Only records with at least this QUAL
+    pub min_qual: Option<f64>,
+
+    // Only records that match all of these
+    pub info: Vec<InfoExpression>,
+
+    // Only records with an alternative allele at least this frequent
+    pub min_allele_frequency: Option<f64>,
+}
+
+impl RecordFilter {
+    /// Whether to build `record` into the graph.
+    ///
+    /// A record without a QUAL, or with `.` for it, doesn't pass a QUAL threshold.
+    /// The frequency of an allele is its `AF` or else its `AC` over `AN`,
+    /// a record with neither passes since we can't tell.
+    pub fn keep(&self, record: &VCFRecord) -> bool {
+        if self.pass_only && record.filter != ["PASS"] {
+            return false;
+        }
+
+        if let Some(min_qual) = self.min_qual {
+            let qual = record
+                .quality
+                .as_ref()
+                .and_then(|qual| qual.parse::<f64>().ok());
+            if !matches!(qual, Some(qual) if qual >= min_qual) {
+                return false;
+            }
+        }
+
+        if !self
+            .info
+            .iter()
+            .all(|expression| expression.matches(record))
+        {
+            return false;
+        }
+
+        match (self.min_allele_frequency, allele_frequencies(record)) {
+            (Some(min), Some(frequencies)) => frequencies.iter().any(|f| *f >= min),
+            _ => true,
+        }
+    }
+}
+
+// The frequency of each alternative allele of a record, None when it doesn't say
+fn allele_frequencies(record: &VCFRecord) -> Option<Vec<f64>> {
+    let numbers = |key: &str| -> Option<Vec<f64>> {
+        let values = record.info.get(key)?;
+        Some(values.iter().filter_map(|v| v.parse().ok()).collect())
+    };
+
+    if let Some(frequencies) = numbers("AF") {
+        return Some(frequencies);
+    }
+
+    let total = numbers("AN")?.first().cloned().filter(|n| *n > 0.0)?;
+    let counts = numbers("AC")?;
+
+    Some(counts.iter().map(|count| count / total).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::variation;
+    use std::io::Cursor;
+    use vcf::VCFReader;
+
+    fn region(contig: &str, start: usize, end: Option<usize>) -> Region {
+        Region {
+            contig: contig.to_owned(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn test_regions() {
+        assert_eq!("chr1".parse::<Region>().unwrap(), region("chr1", 0, None));
+        assert_eq!(
+            "chr1:1,001-2,000".parse::<Region>().unwrap(),
+            region("chr1", 1000, Some(2000))
+        );
+        assert_eq!(
+            "HLA-A*01:01:1-5".parse::<Region>().unwrap(),
+            region("HLA-A*01:01", 0, Some(5))
+        );
+        assert_eq!(
+            "chr1:100-".parse::<Region>().unwrap(),
+            region("chr1", 99, None)
+        );
+
+        for bad in &["", ":1-5", "chr1:0-5", "chr1:5-1", "chr1:a-5"] {
+            assert!(bad.parse::<Region>().is_err(), "{}", bad);
+        }
+
+        let bed = "\
+track name=regions
+# contig\tstart\tend
+chr1\t10\t20\tfirst
+
+chr1\t15\t30
+chr2\t0\t5
+chr1\t0\t5
+";
+        let regions = merge_regions(read_bed(Cursor::new(bed)).unwrap());
+        assert_eq!(
+            regions,
+            vec![
+                region("chr1", 0, Some(5)),
+                region("chr1", 10, Some(30)),
+                region("chr2", 0, Some(5)),
+            ]
+        );
+        assert!(regions[1].contains("chr1", 10, 30));
+        assert!(!regions[1].contains("chr1", 9, 12));
+        assert!(!regions[1].contains("chr2", 10, 12));
+
+        match read_bed(Cursor::new("chr1\t10\n")) {
+            Err(Error::Malformed(msg)) => assert!(msg.starts_with("line 1")),
+            _ => panic!("Expected a malformed input error"),
+        }
+    }
+
+    #[test]
+    fn test_record_filter() {
+        let vcf = Cursor::new(
+            "\
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+chr1	1	pass	A	G	50	PASS	DP=12;AF=0.1
+chr1	2	low	A	G	10	PASS	DP=30;AC=3;AN=10
+chr1	3	failed	A	G	60	q10	DP=8;SVTYPE=SNV;SOMATIC
+chr1	4	unknown	A	G,C	.	.	AC=1,2;AN=4
+",
+        );
+        let mut vcf_reader = VCFReader::new(vcf).unwrap();
+        let records = variation::gen_variations(&mut vcf_reader).unwrap();
+
+        let kept = |filter: &RecordFilter| -> Vec<String> {
+            records
+                .iter()
+                .filter(|record| filter.keep(record))
+                .map(|record| record.id[0].clone())
+                .collect()
+        };
+        let expressions = |expressions: &[&str]| -> Vec<InfoExpression> {
+            expressions.iter().map(|e| e.parse().unwrap()).collect()
+        };
+
+        assert_eq!(kept(&RecordFilter::default()).len(), 4);
+
+        let pass_only = RecordFilter {
+            pass_only: true,
+            ..RecordFilter::default()
+        };
+        assert_eq!(kept(&pass_only), vec!["pass", "low"]);
+
+        let min_qual = RecordFilter {
+            min_qual: Some(20.0),
+            ..RecordFilter::default()
+        };
+        assert_eq!(kept(&min_qual), vec!["pass", "failed"]);
+
+        // A missing QUAL isn't taken as 0
+        let any_qual = RecordFilter {
+            min_qual: Some(0.0),
+            ..RecordFilter::default()
+        };
+        assert_eq!(kept(&any_qual), vec!["pass", "low", "failed"]);
+
+        let info = RecordFilter {
+            info: expressions(&["DP>=10", "AF<0.5"]),
+            ..RecordFilter::default()
+        };
+        assert_eq!(kept(&info), vec!["pass"]);
+
+        let flag = RecordFilter {
+            info: expressions(&["SOMATIC", "SVTYPE!=DEL"]),
+            ..RecordFilter::default()
+        };
+        assert_eq!(kept(&flag), vec!["failed"]);
+
+        // From AF, AC/AN or kept when the record doesn't say
+        let min_allele_frequency = RecordFilter {
+            min_allele_frequency: Some(0.3),
+            ..RecordFilter::default()
+        };
+        assert_eq!(
+            kept(&min_allele_frequency),
+            vec!["low", "failed", "unknown"]
+        );
+
+        for bad in &["", "=5", "DP>"] {
+            assert!(bad.parse::<InfoExpression>().is_err(), "{}", bad);
+        }
+    }
+}
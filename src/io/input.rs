@@ -0,0 +1,103 @@
+//! Reading inputs that may be compressed
+//!
+//! References and VCFs are often gzip compressed, usually as BGZF
+//! which is gzip in blocks so that it can be indexed.
+//! BGZF is a series of gzip members so decompressing all of the members reads it whole.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+
+use flate2::bufread::MultiGzDecoder;
+
+use crate::error::Error;
+
+// The first two bytes of a gzip member
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// An input that's decompressed as it's read if it's gzip or BGZF
+pub enum Input<R: Read> {
+    Plain(BufReader<R>),
+    Gzip(MultiGzDecoder<BufReader<R>>),
+}
+
+impl<R: Read> Input<R> {
+    /// Look at the start of `reader` to tell whether it's compressed
+    pub fn new(reader: R) -> Result<Self, Error> {
+        let mut reader = BufReader::new(reader);
+
+        if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+            Ok(Input::Gzip(MultiGzDecoder::new(reader)))
+        } else {
+            Ok(Input::Plain(reader))
+        }
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        match self {
+            Input::Plain(_) => false,
+            Input::Gzip(_) => true,
+        }
+    }
+}
+
+impl<R: Read> Read for Input<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Input::Plain(reader) => reader.read(buf),
+            Input::Gzip(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// An input opened by its path, a file or stdin
+pub type Reader = Input<Box<dyn Read>>;
+
+/// Open the file at `fp` or stdin when it's `-`
+pub fn open(fp: &str) -> Result<Reader, Error> {
+    let reader: Box<dyn Read> = if fp == "-" {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(fp)?)
+    };
+
+    Input::new(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::{Cursor, Write};
+
+    fn gzip(data: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn read_all<R: Read>(mut input: Input<R>) -> String {
+        let mut data = String::new();
+        input.read_to_string(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_reads_plain_and_compressed_input() {
+        let plain = Input::new(Cursor::new(">chr1\nGATC\n")).unwrap();
+        assert!(!plain.is_compressed());
+        assert_eq!(read_all(plain), ">chr1\nGATC\n");
+
+        let compressed = Input::new(Cursor::new(gzip(">chr1\nGATC\n"))).unwrap();
+        assert!(compressed.is_compressed());
+        assert_eq!(read_all(compressed), ">chr1\nGATC\n");
+
+        // Like BGZF, more than one member
+        let mut members = gzip(">chr1\nGATC\n");
+        members.extend(gzip(">chr2\nTTAG\n"));
+        let compressed = Input::new(Cursor::new(members)).unwrap();
+        assert_eq!(read_all(compressed), ">chr1\nGATC\n>chr2\nTTAG\n");
+
+        assert!(read_all(Input::new(Cursor::new("")).unwrap()).is_empty());
+    }
+}
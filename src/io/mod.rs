@@ -1,6 +1,9 @@
-mod reference;
-mod serialization;
+pub mod reference;
+pub mod serialization;
 
 pub mod cli;
+pub mod fasta;
+pub mod filter;
+pub mod input;
 pub mod types;
 pub mod variation;
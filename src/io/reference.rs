@@ -1,115 +1,403 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::iter;
+use std::ops::Range;
 use std::str;
 
 // Files
 use std::fs::File;
 use std::io::BufReader;
-use std::io::Read;
+use std::io::{Read, Seek};
 
 // VCF
 use vcf::{self, VCFReader, VCFRecord};
 
-use crate::graph::graph;
-use crate::io::types::{Buf, Seeker};
-
-// VCF
-fn open_vcf(fp: &str) -> VCFReader<BufReader<File>> {
-    let f = File::open(fp).unwrap();
-    vcf::VCFReader::new(f).unwrap()
+use crate::error::Error;
+use crate::graph::graph::{self, ConstructOptions};
+use crate::graph::types::Graph;
+use crate::graph::update;
+use crate::io::fasta::IndexedFasta;
+use crate::io::filter::{self, Region};
+use crate::io::input::{self, Input, Reader};
+use crate::io::types::Window;
+use crate::io::variation::{self, Breakend, Breakpoint};
+
+/// Open the VCF at `fp`, stdin when it's `-`, decompressing it if it's gzip or BGZF
+pub fn open_vcf(fp: &str) -> Result<VCFReader<BufReader<Reader>>, Error> {
+    vcf::VCFReader::new(input::open(fp)?).map_err(Error::from)
 }
 
-// Reference
-fn read_ref() {
-    let f = File::open("test/data/RSV/refererence_and_vcf_file/9465113.fa").unwrap();
-    // process_fasta(f);
+/// Build a variation graph out of a reference in FASTA format and variation data in VCF format.
+/// Either of them can be gzip or BGZF compressed.
+/// With the default options construction stops at anything it would warn about.
+///
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// let reference = File::open("reference.fa.gz").unwrap();
+/// let vcf = File::open("variation.vcf.gz").unwrap();
+/// let graph = flex_vg::construct(reference, vcf).unwrap();
+/// ```
+pub fn construct<R: Read, V: Read>(reference: R, vcf: V) -> Result<Graph, Error> {
+    construct_with(reference, vcf, &ConstructOptions::default()).map(|(graph, _)| graph)
 }
 
-// Check that the sequences in the reference are in the same order as
-// they are in the VCF
-fn preprocess_vcf_and_fasta<R: Read>(fasta_data: R, vcf_reader: &mut VCFReader<BufReader<R>>) {
-    println!("Preprocessing...");
-
-    // Get the order of chromosomes in the VCF
-    let mut chromosomes_in_vcf: Vec<String> = Vec::new();
+/// Build a variation graph like [`construct`] with `options` other than the default ones
+///
+/// Along with the graph are warnings of what was left out or kept despite not matching,
+/// the records that don't match the reference with `options.ref_check` other than
+/// `RefCheck::Error` and the haplotypes that can't be followed.
+///
+/// ```rust,no_run
+/// use flex_vg::{
+///     ConstructOptions, HashAlgorithm, HashScheme, PositionEncoding, RecordFilter, RefCheck,
+///     Unphased,
+/// };
+/// use std::fs::File;
+///
+/// let reference = File::open("reference.fa").unwrap();
+/// let vcf = File::open("variation.vcf").unwrap();
+/// let options = ConstructOptions {
+///     scheme: HashScheme::new(HashAlgorithm::Blake3, PositionEncoding::Binary),
+///     ref_check: RefCheck::Warn,
+///     haplotypes: Some(Unphased::Split),
+///     samples: Some(vec![String::from("HG002")]),
+///     filter: RecordFilter {
+///         pass_only: true,
+///         min_qual: Some(30.0),
+///         ..RecordFilter::default()
+///     },
+///     regions: vec!["chr1:1-1,000,000".parse().unwrap()],
+/// };
+/// let (graph, warnings) = flex_vg::construct_with(reference, vcf, &options).unwrap();
+/// for warning in warnings {
+///     eprintln!("Warning: {}", warning);
+/// }
+/// ```
+///
+/// [`construct`]: fn.construct.html
+pub fn construct_with<R: Read, V: Read>(
+    reference: R,
+    vcf: V,
+    options: &ConstructOptions,
+) -> Result<(Graph, Vec<Error>), Error> {
+    let mut vcf_reader = VCFReader::new(Input::new(vcf)?).map_err(Error::from)?;
+    process_fasta(Input::new(reference)?, &mut vcf_reader, options)
+}
 
-    for result_record in vcf_reader.iter() {
-        let record = result_record.unwrap();
-        let chr = record.chromosome;
-        if !chromosomes_in_vcf.contains(&chr) {
-            chromosomes_in_vcf.push(chr);
+/// Build a variation graph like [`construct_with`] out of a FASTA file we can read any part of.
+///
+/// Only the sequences, or with `options.regions` the parts of them, that go into the graph
+/// are read and the order of the sequences in the VCF doesn't matter.
+/// It warns about the same things.
+///
+/// ```rust,no_run
+/// use flex_vg::{ConstructOptions, IndexedFasta};
+/// use std::fs::File;
+///
+/// // Builds reference.fa.gz.fai and reference.fa.gz.gzi if they aren't there
+/// let mut reference = IndexedFasta::open("reference.fa.gz").unwrap();
+/// let vcf = File::open("variation.vcf.gz").unwrap();
+/// let options = ConstructOptions {
+///     regions: vec!["chr6:29,700,000-33,100,000".parse().unwrap()],
+///     ..ConstructOptions::default()
+/// };
+/// let (graph, _) = flex_vg::construct_indexed(&mut reference, vcf, &options).unwrap();
+/// ```
+///
+/// [`construct_with`]: fn.construct_with.html
+pub fn construct_indexed<R: Read + Seek, V: Read>(
+    reference: &mut IndexedFasta<R>,
+    vcf: V,
+    options: &ConstructOptions,
+) -> Result<(Graph, Vec<Error>), Error> {
+    let mut vcf_reader = VCFReader::new(Input::new(vcf)?).map_err(Error::from)?;
+    let names = vcf_reader.header().samples.clone();
+    let samples = select_samples(&names, options.samples.as_deref())?;
+    let mut selection = Selection::new(&mut vcf_reader, options)?;
+
+    let mut vg = Graph::with_scheme(options.scheme);
+    let mut warnings: Vec<Error> = Vec::new();
+
+    for sequence in reference.sequences().to_vec() {
+        let (records, ranges) = selection.take(&sequence.name, sequence.length)?;
+
+        for range in ranges {
+            let bases = reference.fetch(&sequence.name, range.clone())?;
+            let seq = Window::new(range.start, &bases, sequence.length);
+            vg.merge(graph::splitter_within(
+                &sequence.name,
+                seq,
+                &records,
+                &samples,
+                options,
+                &mut warnings,
+            )?);
         }
     }
 
-    // Get the order of sequences in the reference
-    let mut sequences_in_fasta: Vec<String> = Vec::new();
+    selection.finish(&mut vg, options, &mut warnings)?;
+
+    Ok((vg, warnings))
+}
+
+/// Build a variation graph out of the FASTA file at `path` like [`construct_indexed`] when we can
+/// index it and like [`construct_with`], reading it whole, when we can't,
+/// e.g. when its lines aren't all as long or it's gzip but not BGZF.
+/// The warnings start with why the file couldn't be indexed or the indexes couldn't be written.
+///
+/// [`construct_indexed`]: fn.construct_indexed.html
+/// [`construct_with`]: fn.construct_with.html
+pub fn construct_file<V: Read>(
+    path: &str,
+    vcf: V,
+    options: &ConstructOptions,
+) -> Result<(Graph, Vec<Error>), Error> {
+    let (mut warnings, (graph, built)) = match IndexedFasta::open(path) {
+        Ok(mut reference) => (
+            reference.take_warnings(),
+            construct_indexed(&mut reference, vcf, options)?,
+        ),
+        Err(e) => (
+            vec![Error::Unsupported(format!(
+                "reading {} whole, it can't be indexed: {}",
+                path, e
+            ))],
+            construct_with(File::open(path)?, vcf, options)?,
+        ),
+    };
+
+    warnings.extend(built);
+    Ok((graph, warnings))
+}
+
+// File processing
+fn process_fasta<R: Read, V: Read>(
+    fasta_data: R,
+    vcf_reader: &mut VCFReader<BufReader<V>>,
+    options: &ConstructOptions,
+) -> Result<(Graph, Vec<Error>), Error> {
+    let names = vcf_reader.header().samples.clone();
+    let samples = select_samples(&names, options.samples.as_deref())?;
+    let mut selection = Selection::new(vcf_reader, options)?;
+
+    let mut vg = Graph::with_scheme(options.scheme);
+    let mut warnings: Vec<Error> = Vec::new();
+
+    // The callback can't return an error so hold on to the first one and skip the rest
+    let mut result: Result<(), Error> = Ok(());
+    let file_type = Cell::new("FASTA");
+
     needletail::parse_sequence_reader(
         fasta_data,
-        |t| {
-            if t != "FASTA" {
-                panic!("Preprocessing failed: expected a FASTA file but got {}.", t);
-            }
-        },
+        |t| file_type.set(t),
         |seq| {
-            let id = std::str::from_utf8(&seq.id).unwrap();
-            sequences_in_fasta.push(String::from(id))
+            if result.is_err() || file_type.get() != "FASTA" {
+                return;
+            }
+
+            let built = graph::sequence_name(&seq).and_then(|id| {
+                let (records, ranges) = selection.take(id, seq.seq.len())?;
+
+                for range in ranges {
+                    let part = Window::new(range.start, &seq.seq[range.clone()], seq.seq.len());
+                    vg.merge(graph::splitter_within(
+                        id,
+                        part,
+                        &records,
+                        &samples,
+                        options,
+                        &mut warnings,
+                    )?);
+                }
+
+                Ok(())
+            });
+
+            if let Err(e) = built {
+                result = Err(e);
+            }
         },
-    )
-    .expect("Preprocessing the reference failed");
+    )?;
+
+    if file_type.get() != "FASTA" {
+        return Err(Error::Malformed(format!(
+            "expected a FASTA file but got {}",
+            file_type.get()
+        )));
+    }
+    result?;
+
+    selection.finish(&mut vg, options, &mut warnings)?;
 
-    let mut indices: Vec<usize> = Vec::new();
-    //let mut positions: Vec<usize> = Vec::new();
-    // Check that refererences and variation data are in the same order
-    for chr in chromosomes_in_vcf {
-        // panic if chr not in the reference
-        match sequences_in_fasta.binary_search(&chr) {
-            Ok(index) => indices.push(index),
-            Err(e) => panic!("Couldn't find {} in reference", chr),
+    Ok((vg, warnings))
+}
+
+// The records and regions chosen by the options, taken a sequence at a time
+struct Selection {
+    // The records of each sequence other than breakends
+    variations: HashMap<String, Vec<VCFRecord>>,
+
+    // Breakends can join any two sequences so they wait until all of them are in the graph
+    breakends: Vec<VCFRecord>,
+
+    // The regions left to build, None to build all of every sequence
+    regions: Option<Vec<Region>>,
+}
+
+impl Selection {
+    fn new<V: Read>(
+        vcf_reader: &mut VCFReader<BufReader<V>>,
+        options: &ConstructOptions,
+    ) -> Result<Self, Error> {
+        let regions = if options.regions.is_empty() {
+            None
+        } else {
+            Some(filter::merge_regions(options.regions.clone()))
+        };
+
+        let mut variations = variation::group_variations(vcf_reader)?;
+        for records in variations.values_mut() {
+            records.retain(|record| options.filter.keep(record));
+        }
+
+        // Records on sequences without a region are left out with the sequence
+        if let Some(regions) = &regions {
+            variations.retain(|contig, _| regions.iter().any(|r| r.contig == *contig));
+        }
+
+        let mut breakends: Vec<VCFRecord> = Vec::new();
+        for records in variations.values_mut() {
+            let (joins, others): (Vec<VCFRecord>, Vec<VCFRecord>) =
+                records.drain(..).partition(variation::has_breakends);
+            breakends.extend(joins);
+            *records = others;
         }
+
+        if let Some(regions) = &regions {
+            let mut joined: Vec<VCFRecord> = Vec::new();
+            for record in breakends {
+                if joins_within(&record, regions)? {
+                    joined.push(record);
+                }
+            }
+            breakends = joined;
+        }
+
+        Ok(Selection {
+            variations,
+            breakends,
+            regions,
+        })
+    }
+
+    // The records of a sequence `length` long and the ranges of it to build,
+    // none when it isn't in a region
+    fn take(
+        &mut self,
+        id: &str,
+        length: usize,
+    ) -> Result<(Vec<VCFRecord>, Vec<Range<usize>>), Error> {
+        let records = self.variations.remove(id).unwrap_or_default();
+
+        let ranges = match &mut self.regions {
+            None => iter::once(0..length).collect(),
+            Some(regions) => {
+                let (mine, others): (Vec<Region>, Vec<Region>) =
+                    regions.drain(..).partition(|r| r.contig == id);
+                *regions = others;
+                ranges(&mine, length)?
+            }
+        };
+
+        Ok((records, ranges))
     }
 
-    let mut sorted_indices = indices.clone();
-    sorted_indices.sort_unstable();
+    // Join the breakends once all of the sequences are in `vg`
+    fn finish(
+        self,
+        vg: &mut Graph,
+        options: &ConstructOptions,
+        warnings: &mut Vec<Error>,
+    ) -> Result<(), Error> {
+        // Records and regions left over are on sequences that aren't in the reference
+        if let Some(chr) = self.variations.keys().min() {
+            return Err(Error::UnknownContig(chr.clone()));
+        }
 
-    if indices == sorted_indices {
-        println!("Reference and VCF are in order");
-    } else {
-        println!(
-            "Warning: VCF and refererence aren't in order. Some variation data will be skipped."
-        )
+        if let Some(region) = self.regions.iter().flatten().next() {
+            return Err(Error::UnknownContig(region.contig.clone()));
+        }
+
+        update::join_breakends(vg, &self.breakends, options.ref_check, warnings)
     }
 }
 
-// File processing
-// TODO: rename gen_graph/build_graph look at libhandlegraph
-fn process_fasta<R: Read>(fasta_data: R, vcf_reader: &mut VCFReader<BufReader<R>>) -> () {
-    // Should we check for record ID in case the VCF and reference don't match?
+// The samples to add the haplotypes of,
+// all of the samples of the VCF when none are `selected`
+fn select_samples<'a>(
+    names: &'a [String],
+    selected: Option<&'a [String]>,
+) -> Result<Vec<&'a str>, Error> {
+    let selected = match selected {
+        Some(selected) => selected,
+        None => return Ok(names.iter().map(String::as_str).collect()),
+    };
+
+    selected
+        .iter()
+        .map(|sample| match names.contains(sample) {
+            true => Ok(sample.as_str()),
+            false => Err(Error::UnknownSample(sample.clone())),
+        })
+        .collect()
+}
 
-    // let mut v: Vec<&SequenceRecord> = Vec::new();
+// The ranges of a sequence `len` long that its `regions` cover.
+// Regions that run past the end of the sequence stop at it.
+fn ranges(regions: &[Region], len: usize) -> Result<Vec<Range<usize>>, Error> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
 
-    // Temporarily store a VCF record in case the iterator goes forward
-    let mut vcf_recrod_buffer: Buf<VCFRecord> = Buf::new();
+    for region in regions {
+        if region.start >= len {
+            return Err(Error::OutOfRange {
+                contig: region.contig.clone(),
+                position: region.start as u64 + 1,
+            });
+        }
 
-    // I want this to be an array that stores just one VCF record
-    // let mut vcf_record_buf: [VCFRecord; 1];
-    // TODO: how to type annotate an array
+        let end = region.end.map_or(len, |end| end.min(len));
+        if region.start < end {
+            ranges.push(region.start..end);
+        }
+    }
 
-    // Start seeker at pos 0
-    let mut option_seeker: Option<Seeker> = None;
+    Ok(ranges)
+}
 
-    needletail::parse_sequence_reader(
-        fasta_data,
-        |_| {},
-        |seq| {
-            // TODO: use a slice and not a String here
-            graph::splitter(seq, vcf_reader, &mut vcf_recrod_buffer, &mut option_seeker);
-        },
-    )
-    .expect("Parsing failed");
+// Whether both ends of each breakend of a record are in `regions`
+fn joins_within(record: &VCFRecord, regions: &[Region]) -> Result<bool, Error> {
+    let within = |breakpoint: &Breakpoint| {
+        regions.iter().any(|region| {
+            region.contains(&breakpoint.contig, breakpoint.position, breakpoint.position)
+        })
+    };
+
+    Ok(Breakend::new(record)?
+        .iter()
+        .all(|breakend| within(&breakend.from) && within(&breakend.to)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Cursor;
+    use crate::graph::types::{Handle, NodeHash};
+    use crate::io::filter::RecordFilter;
+    use crate::io::variation::{RefCheck, Unphased};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::{Cursor, Write};
+    use std::{env, fs, process};
 
     fn yeild_vcf() -> Cursor<&'static str> {
         Cursor::new(
@@ -130,6 +418,27 @@ chrY	70	rs91489	A	T,TAC	.	PASS	.	GT	0/0	0/1
         )
     }
 
+    // Some of the REF alleles in `yeild_vcf` don't match `yeild_fasta`, keep them
+    fn yeild_options() -> ConstructOptions {
+        ConstructOptions {
+            ref_check: RefCheck::Warn,
+            ..ConstructOptions::default()
+        }
+    }
+
+    // The second record doesn't match the reference, the third is past the end of chr1
+    fn yeild_mismatched_vcf() -> Cursor<&'static str> {
+        Cursor::new(
+            "
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+chr1	10	rs21549	T	C	.	PASS	.
+chr1	20	rs21550	T	C	.	PASS	.
+chr1	50	rs21551	AA	A	.	PASS	.
+",
+        )
+    }
+
     fn yeild_fasta() -> Cursor<&'static str> {
         let fasta_data: Cursor<&str> = Cursor::new(
             "\
@@ -154,44 +463,7 @@ TCTTGTTCTCAAGACCATGGTGAAATTGCTGAAGCCCTGTGTTGCCTCGC
     }
 
     #[test]
-    #[ignore]
-    fn test_can_preprocess_input() {
-        let fasta_data = yeild_fasta();
-        let mut vcf_reader = vcf::VCFReader::new(yeild_vcf()).unwrap();
-
-        // TODO: fix panic because of out of order reference and VCF
-        assert_eq!(preprocess_vcf_and_fasta(fasta_data, &mut vcf_reader), ())
-    }
-
-    #[test]
-    #[should_panic]
-    fn test_panic_when_variation_not_in_reference() {
-        let vcf_data: Cursor<&str> = Cursor::new(
-            "
-##fileformat=VCFv4.2
-##FORMAT=<ID=GT,Number=1,Type=Integer,Description=\"Genotype\">
-##FORMAT=<ID=GP,Number=G,Type=Float,Description=\"Genotype Probabilities\">
-##FORMAT=<ID=PL,Number=G,Type=Float,Description=\"Phred-scaled Genotype Likelihoods\">
-#CHROM	POS    	ID    	REF	ALT 	QUAL	FILTER	INFO	FORMAT	SAMP001	SAMP002
-chr1	10	rs21549	T	C	.	PASS	.	GT	0/0	0/1
-chr1	20	rs21549	T	.	.	PASS	.	GT	0/0	0/1
-chr2	33	rs34146	G	C	.	PASS	.	GT	0/0	0/1
-chr2	40	rs44459	G	TA	.	PASS	.	GT	0/0	0/1
-chr3	23	rs71549	A	CA	.	PASS	.	GT	0/0	0/1
-chrX	23	rs71549	A	CA	.	PASS	.	GT	0/0	0/1
-chrY	70	rs91489	A	T,TAC	.	PASS	.	GT	0/0	0/1
-",
-        );
-
-        let mut vcf_reader = vcf::VCFReader::new(vcf_data).unwrap();
-        let fasta_data = yeild_fasta();
-
-        preprocess_vcf_and_fasta(fasta_data, &mut vcf_reader)
-    }
-
-    #[test]
-    #[should_panic]
-    fn test_panic_when_reference_is_fastq() {
+    fn test_fail_when_reference_is_fastq() {
         let mut vcf_reader = vcf::VCFReader::new(yeild_vcf()).unwrap();
         let fasta_data: Cursor<&str> = Cursor::new(
             "\
@@ -208,7 +480,10 @@ TGTCTCACTCATATAGATGCCCACTTCCTATCCCAGACAAAGCAGA
 ",
         );
 
-        preprocess_vcf_and_fasta(fasta_data, &mut vcf_reader)
+        match process_fasta(fasta_data, &mut vcf_reader, &ConstructOptions::default()) {
+            Err(Error::Malformed(_)) => (),
+            _ => panic!("Expected a malformed input error"),
+        }
     }
 
     #[test]
@@ -216,8 +491,309 @@ TGTCTCACTCATATAGATGCCCACTTCCTATCCCAGACAAAGCAGA
         let reference = yeild_fasta();
         let mut vcf_reader = VCFReader::new(yeild_vcf()).unwrap();
 
-        process_fasta(reference, &mut vcf_reader);
-        // panic!("Intentional panic for print");
+        let (graph, _) = process_fasta(reference, &mut vcf_reader, &yeild_options()).unwrap();
+
+        // chr1: GATCATCGG, T|C, TACACAGCT, A, ...
+        // chrY: ..., T|A|TAC, ...
+        // chrY comes before chrW in the reference but after it in the VCF
+        for (segment, offset) in &[
+            ("GATCATCGG", 0),
+            ("T", 9),
+            ("C", 9),
+            ("TACACAGCT", 10),
+            ("T", 69),
+            ("TAC", 69),
+        ] {
+            let hash = NodeHash::new(segment, *offset);
+            assert!(graph.id_of_hash(hash).is_some());
+        }
+    }
+
+    #[test]
+    fn test_can_construct_graph() {
+        let (graph, _) = construct_with(yeild_fasta(), yeild_vcf(), &yeild_options()).unwrap();
+
+        assert!(!graph.hashmap().is_empty());
+    }
+
+    #[test]
+    fn test_can_construct_from_compressed_input() {
+        let gzip = |data: Cursor<&str>| {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data.get_ref().as_bytes()).unwrap();
+            Cursor::new(encoder.finish().unwrap())
+        };
+
+        let options = yeild_options();
+        let (plain, _) = construct_with(yeild_fasta(), yeild_vcf(), &options).unwrap();
+        let (compressed, _) =
+            construct_with(gzip(yeild_fasta()), gzip(yeild_vcf()), &options).unwrap();
+
+        assert_eq!(compressed.hashmap().len(), plain.hashmap().len());
+        assert_eq!(
+            compressed.path_sequence("chr3").unwrap(),
+            plain.path_sequence("chr3").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_can_construct_from_indexed_reference() {
+        // The name and sequence of each path, ordered by name
+        let paths = |graph: &Graph| {
+            let mut paths: Vec<(String, String)> = graph
+                .paths()
+                .map(|(name, _)| (name.to_owned(), graph.path_sequence(name).unwrap()))
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        let regions = ConstructOptions {
+            regions: vec!["chr1:5-30".parse().unwrap(), "chrY".parse().unwrap()],
+            ..yeild_options()
+        };
+
+        for options in &[yeild_options(), regions] {
+            let mut reference = IndexedFasta::new(Cursor::new(yeild_fasta().into_inner())).unwrap();
+            let (indexed, _) = construct_indexed(&mut reference, yeild_vcf(), options).unwrap();
+            let (streamed, _) = construct_with(yeild_fasta(), yeild_vcf(), options).unwrap();
+
+            assert_eq!(paths(&indexed), paths(&streamed));
+            assert_eq!(indexed.hashmap().len(), streamed.hashmap().len());
+            assert_eq!(indexed.edges().len(), streamed.edges().len());
+        }
+    }
+
+    #[test]
+    fn test_can_construct_from_an_irregularly_wrapped_file() {
+        let path = env::temp_dir().join(format!("flex-vg-{}-irregular.fa", process::id()));
+        let path = path.to_str().unwrap();
+
+        // The lines of chr1 aren't all as long so it can't be indexed
+        fs::write(path, ">chr1\nGATCAT\nCGGTTACA\nCAGCTA\n").unwrap();
+        let vcf = "##fileformat=VCFv4.2\n#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+                   chr1\t10\trs1\tT\tC\t.\tPASS\t.\n";
+        let built = construct_file(path, Cursor::new(vcf), &ConstructOptions::default());
+        fs::remove_file(path).unwrap();
+
+        let (graph, warnings) = built.unwrap();
+        match &warnings[..] {
+            [Error::Unsupported(msg)] => assert!(msg.contains("Malformed input"), "{}", msg),
+            _ => panic!("Expected a warning that it can't be indexed"),
+        }
+        assert_eq!(graph.hashmap().len(), 4);
+        assert_eq!(graph.path_sequence("chr1").unwrap(), "GATCATCGGTTACACAGCTA");
+
+        // Once the lines are as long it's indexed
+        fs::write(path, ">chr1\nGATCATCG\nGTTACACA\nGCTA\n").unwrap();
+        let built = construct_file(path, Cursor::new(vcf), &ConstructOptions::default());
+        fs::remove_file(path).unwrap();
+        fs::remove_file(format!("{}.fai", path)).unwrap();
+
+        let (graph, warnings) = built.unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(graph.path_sequence("chr1").unwrap(), "GATCATCGGTTACACAGCTA");
+    }
+
+    #[test]
+    fn test_fail_when_construction_variation_not_in_reference() {
+        let vcf_data: Cursor<&str> = Cursor::new(
+            "
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+chr1	10	rs21549	T	C	.	PASS	.
+chrX	23	rs71549	A	CA	.	PASS	.
+",
+        );
+
+        match construct(yeild_fasta(), vcf_data) {
+            Err(Error::UnknownContig(chr)) => assert_eq!(chr, "chrX"),
+            _ => panic!("Expected an unknown contig error"),
+        }
+    }
+
+    #[test]
+    fn test_fail_when_variation_is_outside_reference() {
+        let vcf_data: Cursor<&str> = Cursor::new(
+            "
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+chr1	51	rs21549	T	C	.	PASS	.
+",
+        );
+
+        match construct(yeild_fasta(), vcf_data) {
+            Err(Error::OutOfRange { contig, position }) => {
+                assert_eq!(contig, "chr1");
+                assert_eq!(position, 51);
+            }
+            _ => panic!("Expected an out of range error"),
+        }
+    }
+
+    #[test]
+    fn test_fail_when_reference_allele_does_not_match() {
+        match construct(yeild_fasta(), yeild_mismatched_vcf()) {
+            Err(Error::RefMismatch {
+                contig, position, ..
+            }) => assert_eq!((contig.as_str(), position), ("chr1", 20)),
+            _ => panic!("Expected a reference mismatch error"),
+        }
+    }
+
+    #[test]
+    fn test_ref_check_modes() {
+        let construct_checked = |ref_check: RefCheck| {
+            let options = ConstructOptions {
+                ref_check,
+                ..ConstructOptions::default()
+            };
+            construct_with(yeild_fasta(), yeild_mismatched_vcf(), &options)
+        };
+
+        match construct_checked(RefCheck::Error) {
+            Err(Error::RefMismatch {
+                contig,
+                position,
+                expected,
+                found,
+            }) => {
+                assert_eq!((contig.as_str(), position), ("chr1", 20));
+                assert_eq!((expected.as_str(), found.as_str()), ("A", "T"));
+            }
+            _ => panic!("Expected a reference mismatch error"),
+        }
+
+        // The mismatch is left out or kept, the record past the end of chr1 is always left out
+        let (skipped, skips) = construct_checked(RefCheck::Skip).unwrap();
+        let (kept, warnings) = construct_checked(RefCheck::Warn).unwrap();
+        let has_node = |graph: &Graph, segment: &str, offset: usize| {
+            graph.id_of_hash(NodeHash::new(segment, offset)).is_some()
+        };
+
+        assert!(has_node(&skipped, "C", 9) && has_node(&kept, "C", 9));
+        assert!(!has_node(&skipped, "C", 19) && has_node(&kept, "C", 19));
+        assert!(has_node(&kept, "A", 19));
+        assert_eq!(kept.path_sequence("chr1").unwrap().len(), 50);
+
+        // Both are reported either way
+        assert_eq!((skips.len(), warnings.len()), (2, 2));
+    }
+
+    #[test]
+    fn test_joins_breakends() {
+        let breakends = |mate: &str| {
+            Cursor::new(format!(
+                "
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+chr1	10	bnd_1	T	T[chr2:20[	.	PASS	MATEID=bnd_2
+chr1	30	bnd_3	G	GAA]chr2:40]	.	PASS	.
+chr2	20	bnd_2	G	{}	.	PASS	MATEID=bnd_1
+",
+                mate
+            ))
+        };
+
+        let graph = construct(yeild_fasta(), breakends("]chr1:10]G")).unwrap();
+        let node = |contig: &str, offset: usize| {
+            let n = graph
+                .hashmap()
+                .values()
+                .find(|n| n.reference() == Some(contig) && n.offset() == offset);
+            n.unwrap().id
+        };
+
+        // chr1 joins the forward strand of chr2 after 10 and its reverse strand after 30
+        let inserted = graph.id_of_hash(NodeHash::new("AA", 30)).unwrap();
+        let edges = [
+            (
+                Handle::forward(node("chr1", 0)),
+                Handle::forward(node("chr2", 19)),
+            ),
+            (Handle::forward(node("chr1", 10)), Handle::forward(inserted)),
+            (Handle::forward(inserted), Handle::reverse(node("chr2", 19))),
+        ];
+        for (from, to) in edges.iter() {
+            assert!(graph.has_edge(*from, *to).unwrap());
+        }
+
+        // Mates are a single edge and the references are still paths
+        assert_eq!(graph.edges().len(), 7);
+        assert_eq!(graph.path_sequence("chr1").unwrap().len(), 50);
+        assert_eq!(graph.path_sequence("chr2").unwrap().len(), 50);
+
+        match construct(yeild_fasta(), breakends("]chr1:11]G")) {
+            Err(Error::Malformed(_)) => (),
+            _ => panic!("Expected a malformed input error"),
+        }
+    }
+
+    #[test]
+    fn test_selects_records_samples_and_regions() {
+        let vcf_data = "
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO	FORMAT	A	B
+chr1	10	snv	T	C	50	PASS	AF=0.5	GT	0|0	1|1
+chr1	20	failed	A	G	50	q10	AF=0.5	GT	0|1	0|1
+chr1	25	rare	C	T	50	PASS	AF=0.01	GT	0|1	0|0
+chr1	40	outside	T	A	50	PASS	AF=0.5	GT	0|1	0|1
+chr2	33	other	A	C	50	PASS	AF=0.5	GT	0|1	0|1
+chrX	23	unknown	A	CA	50	PASS	AF=0.5	GT	0|1	0|1
+";
+        let construct_selected = |samples: &[&str], regions: &[&str]| {
+            let options = ConstructOptions {
+                haplotypes: Some(Unphased::Skip),
+                samples: Some(samples.iter().map(|s| String::from(*s)).collect()),
+                filter: RecordFilter {
+                    pass_only: true,
+                    min_allele_frequency: Some(0.1),
+                    ..RecordFilter::default()
+                },
+                regions: regions.iter().map(|r| r.parse().unwrap()).collect(),
+                ..ConstructOptions::default()
+            };
+            construct_with(yeild_fasta(), Cursor::new(vcf_data), &options).map(|(graph, _)| graph)
+        };
+
+        let graph = construct_selected(&["B"], &["chr1:5-30"]).unwrap();
+        let mut paths: Vec<&str> = graph.paths().map(|(name, _)| name).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["B#1#chr1[4]", "B#2#chr1[4]", "chr1[4-30]"]);
+
+        assert_eq!(
+            graph.path_sequence("chr1[4-30]").unwrap(),
+            "ATCGGTTACACAGCTAAGTTTGACCG"
+        );
+        assert_eq!(
+            graph.path_sequence("B#1#chr1[4]").unwrap(),
+            "ATCGGCTACACAGCTAAGTTTGACCG"
+        );
+
+        // Only the SNV passes and is in the region
+        let has_node = |segment: &str, offset: usize| {
+            graph.id_of_hash(NodeHash::new(segment, offset)).is_some()
+        };
+        assert!(has_node("C", 9));
+        assert!(!has_node("G", 19) && !has_node("T", 24) && !has_node("A", 39));
+        assert_eq!(graph.hashmap().len(), 4);
+
+        match construct_selected(&["Z"], &["chr1"]) {
+            Err(Error::UnknownSample(sample)) => assert_eq!(sample, "Z"),
+            _ => panic!("Expected an unknown sample error"),
+        }
+
+        match construct_selected(&[], &["chrZ:1-10"]) {
+            Err(Error::UnknownContig(chr)) => assert_eq!(chr, "chrZ"),
+            _ => panic!("Expected an unknown contig error"),
+        }
+
+        match construct_selected(&[], &["chr2:60-70"]) {
+            Err(Error::OutOfRange { contig, position }) => {
+                assert_eq!((contig.as_str(), position), ("chr2", 60))
+            }
+            _ => panic!("Expected an out of range error"),
+        }
     }
 
     // TODO: Is this test necessary? It tests nothing in *flex-vg* code
@@ -228,18 +804,12 @@ TGTCTCACTCATATAGATGCCCACTTCCTATCCCAGACAAAGCAGA
         // VCF Header
         let samples = &vcf_reader.header().samples;
         let items = &vcf_reader.header().items;
-        let expected_samples = vec!["SAMP001", "SAMP002"];
+        let expected_samples = ["SAMP001", "SAMP002"];
 
         // We don't care about the ordering of the samples.
         // We only care that they exist.
-        // TODO: Can these be compared any better?
         for sample in samples {
-            let sample = sample.as_str();
-
-            assert!(match expected_samples.binary_search(&sample) {
-                Ok(_) => true,
-                _ => false,
-            });
+            assert!(expected_samples.contains(&sample.as_str()));
         }
 
         // VCF Records
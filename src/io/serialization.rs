@@ -1,23 +1,79 @@
 //! Serialize with serde CBOR
 
-use std::collections::HashMap;
-use std::error::Error;
-use std::fs::File;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
 
-fn serialize_graph(filename: &str, my_map: &HashMap<String, u32>) -> Result<(), Box<dyn Error>> {
-    // serialize
-    let bin_file = File::create(filename)?;
-    serde_cbor::to_writer(bin_file, my_map)?;
+use crate::error::Error;
+use crate::graph::types::Graph;
+
+// Bump when the layout of the serialized graph changes
+const FORMAT_VERSION: u32 = 5;
+
+// What goes on disk: the graph and what we need to read it back.
+// The graph is only decoded once we know it's in a layout we can read.
+#[derive(Deserialize)]
+struct GraphFile {
+    version: u32,
+    graph: serde_cbor::Value,
+}
+
+/// Write the graph in CBOR
+pub fn serialize_graph<W: Write>(writer: W, graph: &Graph) -> Result<(), Error> {
+    #[derive(Serialize)]
+    struct GraphFileRef<'g> {
+        version: u32,
+        graph: &'g Graph,
+    }
+
+    let f = GraphFileRef {
+        version: FORMAT_VERSION,
+        graph,
+    };
+    serde_cbor::to_writer(writer, &f)?;
 
     Ok(())
 }
 
+/// Read a graph written by [`serialize_graph`]
+///
+/// [`serialize_graph`]: fn.serialize_graph.html
+pub fn deserialize_graph<R: Read>(reader: R) -> Result<Graph, Error> {
+    let f: GraphFile = serde_cbor::from_reader(reader)?;
+
+    if f.version != FORMAT_VERSION {
+        return Err(Error::Unsupported(format!(
+            "graph format version {}, expected {}",
+            f.version, FORMAT_VERSION
+        )));
+    }
+
+    Ok(serde_cbor::value::from_value(f.graph)?)
+}
+
 #[cfg(test)]
 mod tests {
     use serde_test::{assert_tokens, Token};
-    use std::fs;
+    use std::collections::HashMap;
+    use std::fs::{self, File};
+    use std::io::Cursor;
 
     use super::*;
+    use crate::graph::graph::ConstructOptions;
+    use crate::graph::types::{HashAlgorithm, HashScheme, NodeHash, NodeId, PositionEncoding};
+    use crate::io::reference;
+
+    fn yeild_graph() -> Graph {
+        let fasta = Cursor::new(">chr1\nGATCATCGGTTACACAGCTA\n");
+        let vcf = Cursor::new(
+            "\
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+chr1	5	rs21549	A	G,TT	.	PASS	.
+",
+        );
+
+        reference::construct(fasta, vcf).unwrap()
+    }
 
     #[test]
     fn test_me() {
@@ -40,20 +96,78 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_node_hash_is_bytes() {
+        let hash = NodeHash::new("GATTACA", 23);
+        let bytes: &[u8; 32] = std::borrow::Borrow::borrow(&hash);
+
+        // Token::Bytes needs a 'static slice
+        let bytes: &'static [u8] = Box::leak(Box::new(*bytes));
+        assert_tokens(&hash, &[Token::Bytes(bytes)]);
+    }
+
+    #[test]
+    fn test_node_id_is_a_number() {
+        assert_tokens(
+            &NodeId::new(23),
+            &[Token::NewtypeStruct { name: "NodeId" }, Token::U64(23)],
+        );
+    }
+
     #[test]
     fn creates_and_saves_graph() {
-        let mut my_map: HashMap<String, u32> = HashMap::new();
-        my_map.insert(String::from("age"), 32);
+        let graph = yeild_graph();
         let filename = "ferris.cbor";
 
-        serialize_graph(filename, &my_map);
+        serialize_graph(File::create(filename).unwrap(), &graph).unwrap();
 
         // deserialize
         let f = File::open(filename).unwrap();
-        let my_deserialized_map: HashMap<String, u32> = serde_cbor::from_reader(f).unwrap();
+        let deserialized_graph = deserialize_graph(f).unwrap();
+
+        assert_eq!(deserialized_graph, graph);
+
+        fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_keeps_hash_scheme() {
+        let fasta = Cursor::new(">chr1\nGATCATCGGTTACACAGCTA\n");
+        let vcf =
+            Cursor::new("##fileformat=VCFv4.2\n#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n");
+        let scheme = HashScheme::new(HashAlgorithm::Xxh3, PositionEncoding::Binary);
+        let options = ConstructOptions {
+            scheme,
+            ..ConstructOptions::default()
+        };
+        let (graph, _) = reference::construct_with(fasta, vcf, &options).unwrap();
+
+        let mut buf: Vec<u8> = Vec::new();
+        serialize_graph(&mut buf, &graph).unwrap();
+        let deserialized_graph = deserialize_graph(Cursor::new(buf)).unwrap();
+
+        assert_eq!(deserialized_graph.scheme(), scheme);
+        assert_eq!(deserialized_graph, graph);
+    }
+
+    #[test]
+    fn test_reject_other_formats() {
+        #[derive(Serialize)]
+        struct NewerGraphFile {
+            version: u32,
+            graph: Vec<u8>,
+        }
 
-        assert_eq!(my_deserialized_map, my_map);
+        let mut buf: Vec<u8> = Vec::new();
+        let f = NewerGraphFile {
+            version: FORMAT_VERSION + 1,
+            graph: Vec::new(),
+        };
+        serde_cbor::to_writer(&mut buf, &f).unwrap();
 
-        fs::remove_file(filename);
+        match deserialize_graph(Cursor::new(buf)) {
+            Err(Error::Unsupported(_)) => (),
+            _ => panic!("Expected an unsupported input error"),
+        }
     }
 }
@@ -1,6 +1,8 @@
 //! Holds the previous position we sliced the reference at
 //! Helps to *slice* the reference
 
+use std::ops::{Index, Range, RangeFrom};
+
 pub struct Seeker {
     chromosome: String,
     previous_position: u64,
@@ -23,6 +25,70 @@ impl Seeker {
     }
 }
 
+/// The bases of a sequence from `start` on, sliced with positions on the whole sequence.
+/// Lets us build part of a sequence without reading the rest of it.
+#[derive(Clone, Copy)]
+pub struct Window<'a> {
+    start: usize,
+    bases: &'a [u8],
+    // The length of the whole sequence
+    length: usize,
+}
+
+impl<'a> Window<'a> {
+    pub fn new(start: usize, bases: &'a [u8], length: usize) -> Self {
+        Window {
+            start,
+            bases,
+            length,
+        }
+    }
+
+    // All of a sequence
+    pub fn whole(bases: &'a [u8]) -> Self {
+        Window::new(0, bases, bases.len())
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.start + self.bases.len()
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_whole(&self) -> bool {
+        self.start == 0 && self.bases.len() == self.length
+    }
+
+    // The bases between `range.start` and `range.end`, None if they aren't all in the window
+    pub fn get(&self, range: Range<usize>) -> Option<&'a [u8]> {
+        let start = range.start.checked_sub(self.start)?;
+        let end = range.end.checked_sub(self.start)?;
+        self.bases.get(start..end)
+    }
+}
+
+impl<'a> Index<Range<usize>> for Window<'a> {
+    type Output = [u8];
+
+    fn index(&self, range: Range<usize>) -> &[u8] {
+        &self.bases[range.start - self.start..range.end - self.start]
+    }
+}
+
+impl<'a> Index<RangeFrom<usize>> for Window<'a> {
+    type Output = [u8];
+
+    fn index(&self, range: RangeFrom<usize>) -> &[u8] {
+        &self.bases[range.start - self.start..]
+    }
+}
+
 pub struct Buf<T> {
     value: Option<T>,
 }
@@ -65,6 +131,20 @@ mod tests {
         assert_eq!(val, read_val);
     }
 
+    // Window
+    #[test]
+    fn test_window_slices_with_positions_on_the_sequence() {
+        let window = Window::new(4, b"ATCGG", 20);
+        assert_eq!((window.start(), window.end()), (4, 9));
+        assert!(!window.is_whole() && Window::whole(b"GATC").is_whole());
+
+        assert_eq!(&window[5..7], b"TC");
+        assert_eq!(&window[7..], b"GG");
+        assert_eq!(window.get(4..9), Some(&b"ATCGG"[..]));
+        assert_eq!(window.get(3..5), None);
+        assert_eq!(window.get(8..10), None);
+    }
+
     // Seeker
     //#[test]
     //#[ignore]
@@ -1 +1,1059 @@
+//! Variation data
 
+use std::collections::HashMap;
+use std::io::BufReader;
+use std::io::Read;
+
+use vcf::{VCFReader, VCFRecord};
+
+use crate::error::Error;
+use crate::graph::types::Orientation;
+use crate::io::types::Window;
+
+// The allele of a haplotype that's deleted by an overlapping record
+pub(crate) const SPANNING_DELETION: &str = "*";
+
+/// The alleles of a VCF record as the sequences that replace each other on the reference
+///
+/// VCF writes indels with a base of the reference that all the alleles share,
+/// the one before the event or the one after it when the event is at the start of a sequence.
+/// It isn't part of the variation so it's left out.
+/// This makes the reference allele of an insertion and the alternative allele of a deletion empty.
+#[derive(Debug, PartialEq)]
+pub struct Alleles {
+    // 0 based position of the reference allele on its sequence
+    pub start: usize,
+    pub reference: String,
+    // Without `.` for no alternative allele, `*` for a spanning deletion,
+    // symbolic and breakend alleles and repeats
+    pub alternatives: Vec<String>,
+}
+
+impl Alleles {
+    pub fn new(record: &VCFRecord) -> Self {
+        let mut start = (record.position as usize).saturating_sub(1);
+        let mut reference = record.reference.clone();
+        let mut alternatives: Vec<String> = Vec::new();
+
+        for alt in record.alternative.iter() {
+            if alt != "."
+                && alt != SPANNING_DELETION
+                && symbolic(alt).is_none()
+                && !is_breakend(alt)
+                && *alt != reference
+                && !alternatives.contains(alt)
+            {
+                alternatives.push(alt.clone());
+            }
+        }
+
+        let is_indel = alternatives.iter().any(|alt| alt.len() != reference.len());
+        let shared = |base: fn(&str) -> Option<char>| {
+            let first = base(&reference);
+            first.is_some() && alternatives.iter().all(|alt| base(alt) == first)
+        };
+
+        if is_indel && shared(|allele| allele.chars().next()) {
+            start += 1;
+            reference.remove(0);
+            for alt in alternatives.iter_mut() {
+                alt.remove(0);
+            }
+        } else if is_indel && shared(|allele| allele.chars().last()) {
+            reference.pop();
+            for alt in alternatives.iter_mut() {
+                alt.pop();
+            }
+        }
+
+        Alleles {
+            start,
+            reference,
+            alternatives,
+        }
+    }
+
+    // 0 based position just past the reference allele
+    pub fn end(&self) -> usize {
+        self.start + self.reference.len()
+    }
+
+    // Whether one of the alleles is empty and its nodes are skipped
+    pub fn has_empty(&self) -> bool {
+        self.reference.is_empty() || self.alternatives.iter().any(|alt| alt.is_empty())
+    }
+
+    // Whether `other`, which doesn't start before these alleles, overlaps them.
+    // Alleles that start at the same position overlap even when one of them is empty.
+    pub fn overlaps(&self, other: &Alleles) -> bool {
+        other.start == self.start || other.start < self.end()
+    }
+
+    // Combine overlapping alleles into alleles that span both.
+    // The alternative alleles of each are extended with the reference on either side of them
+    // and the reference allele is taken from `sequence`, the sequence both are on.
+    pub fn merge(self, other: Alleles, sequence: &Window) -> Alleles {
+        let start = self.start.min(other.start);
+        let end = self.end().max(other.end());
+        let reference = String::from_utf8_lossy(&sequence[start..end]).into_owned();
+
+        let mut alternatives: Vec<String> = Vec::new();
+        for alleles in [self, other].iter() {
+            let before = &reference[..alleles.start - start];
+            let after = &reference[alleles.end() - start..];
+
+            for alt in alleles.alternatives.iter() {
+                let alt = format!("{}{}{}", before, alt, after);
+                if alt != reference && !alternatives.contains(&alt) {
+                    alternatives.push(alt);
+                }
+            }
+        }
+
+        Alleles {
+            start,
+            reference,
+            alternatives,
+        }
+    }
+}
+
+/// The sites of variation on a sequence in order, given the alleles of its records.
+///
+/// Records whose reference alleles overlap, such as a deletion over a SNP,
+/// are merged into a single site whose alleles are those of all of them.
+pub fn sites(mut alleles: Vec<Alleles>, sequence: &Window) -> Vec<Alleles> {
+    // Leaving out anchor bases can change the order of records so sort them again
+    alleles.sort_by_key(|a| a.start);
+
+    let mut sites: Vec<Alleles> = Vec::new();
+    for a in alleles {
+        match sites.pop() {
+            Some(site) if site.overlaps(&a) => sites.push(site.merge(a, sequence)),
+            site => {
+                sites.extend(site);
+                sites.push(a);
+            }
+        }
+    }
+
+    sites
+}
+
+/// An allele as the sequence that replaces the reference between `start` and `end`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Replacement {
+    pub start: usize,
+    pub end: usize,
+    pub sequence: String,
+}
+
+/// The alternative alleles of a record in VCF order as what they replace the reference with,
+/// without the bases VCF anchors them with. Allele `i` of a genotype is `i - 1` here.
+///
+/// None for alleles that don't replace a part of the reference
+/// such as `.`, breakends, inversions and duplications.
+pub fn replacements(record: &VCFRecord) -> Result<Vec<Option<Replacement>>, Error> {
+    let alleles = Alleles::new(record);
+
+    // The anchor bases `Alleles` left out before and after the alleles
+    let before = alleles.start - (record.position as usize).saturating_sub(1);
+    let after = record.reference.len() - before - alleles.reference.len();
+
+    let mut replacements: Vec<Option<Replacement>> = Vec::new();
+    for alt in record.alternative.iter() {
+        let replacement = match StructuralVariant::of_allele(record, alt)? {
+            Some(StructuralVariant::Deletion { start, end }) => Some(Replacement {
+                start,
+                end,
+                sequence: String::new(),
+            }),
+            Some(StructuralVariant::Insertion { start, sequence }) => Some(Replacement {
+                start,
+                end: start,
+                sequence,
+            }),
+            Some(_) => None,
+            None if alt == "."
+                || alt == SPANNING_DELETION
+                || symbolic(alt).is_some()
+                || is_breakend(alt) =>
+            {
+                None
+            }
+            None => alt
+                .get(before..alt.len().saturating_sub(after))
+                .map(|sequence| Replacement {
+                    start: alleles.start,
+                    end: alleles.end(),
+                    sequence: sequence.to_owned(),
+                }),
+        };
+
+        replacements.push(replacement);
+    }
+
+    Ok(replacements)
+}
+
+/// The alleles of a sample at a record, one for each of its haplotypes
+#[derive(Debug, PartialEq, Clone)]
+pub struct Genotype {
+    // The index of the allele of each haplotype, 0 for the reference, None where it's missing
+    pub alleles: Vec<Option<usize>>,
+    // Whether we know which haplotype each allele is on.
+    // A homozygous genotype is the same whichever way it's phased.
+    pub phased: bool,
+}
+
+impl Genotype {
+    /// The `GT` of `sample` at a record, None if it has none
+    pub fn new(record: &VCFRecord, sample: &str) -> Result<Option<Self>, Error> {
+        let value = match record.call.get(sample).and_then(|fields| fields.get("GT")) {
+            Some(values) => values.join(","),
+            None => return Ok(None),
+        };
+
+        let mut alleles: Vec<Option<usize>> = Vec::new();
+        for allele in value.split(&['|', '/'][..]) {
+            match allele {
+                "." => alleles.push(None),
+                _ => alleles.push(Some(allele.parse().map_err(|_| {
+                    Error::Malformed(format!(
+                        "bad genotype {} at {}:{}",
+                        value, record.chromosome, record.position
+                    ))
+                })?)),
+            }
+        }
+
+        let homozygous = alleles.windows(2).all(|pair| pair[0] == pair[1]);
+
+        Ok(Some(Genotype {
+            phased: !value.contains('/') || homozygous,
+            alleles,
+        }))
+    }
+}
+
+/// What to do with a genotype we can't follow a haplotype through
+/// because it isn't phased, it's missing or its allele isn't in the graph
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Unphased {
+    // Leave the haplotype out on the sequence of the record
+    #[default]
+    Skip,
+    // End the path of the haplotype before the record and start another after it
+    Split,
+    // Follow the reference allele
+    Reference,
+}
+
+impl std::str::FromStr for Unphased {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Unphased::Skip),
+            "split" => Ok(Unphased::Split),
+            "reference" => Ok(Unphased::Reference),
+            _ => Err(Error::Malformed(format!(
+                "{} isn't one of skip, split or reference",
+                s
+            ))),
+        }
+    }
+}
+
+/// A structural variant written as a symbolic allele such as `<DEL>`
+///
+/// Positions are 0 based and leave out the base before the variant
+/// that the record starts with, like the anchor base of other indels.
+#[derive(Debug, PartialEq, Clone)]
+pub enum StructuralVariant {
+    // The reference between `start` and `end` is left out
+    Deletion { start: usize, end: usize },
+    // `sequence` goes in before `start`
+    Insertion { start: usize, sequence: String },
+    // The reference between `start` and `end` is read on the other strand
+    Inversion { start: usize, end: usize },
+    // The reference between `start` and `end` is repeated right after itself
+    Duplication { start: usize, end: usize },
+}
+
+impl StructuralVariant {
+    /// The structural variants of the symbolic alleles of a record.
+    ///
+    /// They end at the `END` of the record or `SVLEN` bases after they start.
+    /// The sequence of an insertion is in `SVINSSEQ`, we can't place one without it.
+    /// Symbolic alleles of other types such as `<CNV>` are left out.
+    pub fn new(record: &VCFRecord) -> Result<Vec<Self>, Error> {
+        let mut variants: Vec<StructuralVariant> = Vec::new();
+
+        for alt in record.alternative.iter() {
+            if let Some(variant) = StructuralVariant::of_allele(record, alt)? {
+                if !variants.contains(&variant) {
+                    variants.push(variant);
+                }
+            }
+        }
+
+        Ok(variants)
+    }
+
+    // The structural variant of a single allele of a record, None if it isn't one
+    fn of_allele(record: &VCFRecord, allele: &str) -> Result<Option<Self>, Error> {
+        let start = record.position as usize;
+
+        let variant = match symbolic(allele) {
+            Some("DEL") => StructuralVariant::Deletion {
+                start,
+                end: end_of(record, start)?,
+            },
+            Some("INV") => StructuralVariant::Inversion {
+                start,
+                end: end_of(record, start)?,
+            },
+            Some("DUP") => StructuralVariant::Duplication {
+                start,
+                end: end_of(record, start)?,
+            },
+            Some("INS") => match info(record, "SVINSSEQ") {
+                Some(sequence) => StructuralVariant::Insertion {
+                    start,
+                    sequence: sequence.to_owned(),
+                },
+                None => {
+                    return Err(Error::Unsupported(format!(
+                        "{} at {}:{} has no SVINSSEQ",
+                        allele, record.chromosome, record.position
+                    )))
+                }
+            },
+            _ => return Ok(None),
+        };
+
+        Ok(Some(variant))
+    }
+
+    // The part of the reference the variant is on, from its start to just past its end
+    pub fn span(&self) -> (usize, usize) {
+        match self {
+            StructuralVariant::Deletion { start, end }
+            | StructuralVariant::Inversion { start, end }
+            | StructuralVariant::Duplication { start, end } => (*start, *end),
+            StructuralVariant::Insertion { start, .. } => (*start, *start),
+        }
+    }
+
+    // The alleles of a deletion or an insertion given `reference`, the reference in its span.
+    // Inversions and duplications aren't alleles but edges between the nodes of the reference.
+    pub fn alleles(&self, reference: &str) -> Option<Alleles> {
+        match self {
+            StructuralVariant::Deletion { start, .. } => Some(Alleles {
+                start: *start,
+                reference: reference.to_owned(),
+                alternatives: vec![String::new()],
+            }),
+            StructuralVariant::Insertion { start, sequence } => Some(Alleles {
+                start: *start,
+                reference: String::new(),
+                alternatives: vec![sequence.clone()],
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// One end of the adjacency of a breakend
+///
+/// `position` is a 0 based position between two bases of `contig`.
+/// A forward breakpoint is on the strand of the reference: an adjacency leaves it
+/// through the base before `position` and enters it through the base after.
+/// A reverse breakpoint is on the other strand and the other way around.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Breakpoint {
+    pub contig: String,
+    pub position: usize,
+    pub orientation: Orientation,
+}
+
+impl Breakpoint {
+    fn new(contig: &str, position: usize, orientation: Orientation) -> Self {
+        Breakpoint {
+            contig: contig.to_owned(),
+            position,
+            orientation,
+        }
+    }
+
+    // The same breakpoint read on the other strand
+    fn flip(&self) -> Self {
+        let orientation = match self.orientation {
+            Orientation::Forward => Orientation::Reverse,
+            Orientation::Reverse => Orientation::Forward,
+        };
+
+        Breakpoint::new(&self.contig, self.position, orientation)
+    }
+}
+
+/// An adjacency between two parts of the reference written as a breakend allele
+/// such as `G[chr2:321682[`, often a translocation between sequences.
+///
+/// The adjacency goes from `from` through `inserted` to `to`.
+/// A breakend and its mate are the same adjacency seen from either end.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Breakend {
+    // The IDs of the record and its mate
+    pub id: Option<String>,
+    pub mate: Option<String>,
+    pub from: Breakpoint,
+    pub to: Breakpoint,
+    pub inserted: String,
+}
+
+impl Breakend {
+    /// The breakends of the alleles of a record.
+    ///
+    /// Single breakends such as `G.` have no other end and are left out.
+    pub fn new(record: &VCFRecord) -> Result<Vec<Self>, Error> {
+        let malformed = |alt: &str| {
+            Error::Malformed(format!(
+                "bad breakend {} at {}:{}",
+                alt, record.chromosome, record.position
+            ))
+        };
+
+        let contig = &record.chromosome[..];
+        let position = record.position as usize;
+        let mut breakends: Vec<Breakend> = Vec::new();
+
+        for alt in record.alternative.iter() {
+            let open = match alt.find(&['[', ']'][..]) {
+                Some(open) => open,
+                None => continue,
+            };
+
+            // The mate is written between two brackets of the same kind
+            let bracket = &alt[open..=open];
+            let close = match alt[open + 1..].find(bracket) {
+                Some(close) => open + 1 + close,
+                None => return Err(malformed(alt)),
+            };
+
+            let (mate_contig, mate_position) = match alt[open + 1..close].rfind(':') {
+                Some(colon) => (
+                    &alt[open + 1..open + 1 + colon],
+                    &alt[open + 2 + colon..close],
+                ),
+                None => return Err(malformed(alt)),
+            };
+            let mate_position: usize = mate_position.parse().map_err(|_| malformed(alt))?;
+
+            // The bases of the record are before the mate in `t[p[` and `t]p]`
+            // and after it in `]p]t` and `[p[t`.
+            // Those other than the reference are inserted between the two.
+            let (from, to, inserted) = if open > 0 && close == alt.len() - 1 {
+                let inserted = alt[..open]
+                    .get(record.reference.len()..)
+                    .unwrap_or_default();
+                let to = match bracket {
+                    "[" => Breakpoint::new(
+                        mate_contig,
+                        mate_position.saturating_sub(1),
+                        Orientation::Forward,
+                    ),
+                    _ => Breakpoint::new(mate_contig, mate_position, Orientation::Reverse),
+                };
+
+                (
+                    Breakpoint::new(contig, position, Orientation::Forward),
+                    to,
+                    inserted,
+                )
+            } else if open == 0 {
+                let bases = &alt[close + 1..];
+                let inserted = bases
+                    .get(..bases.len().saturating_sub(record.reference.len()))
+                    .unwrap_or_default();
+                let from = match bracket {
+                    "]" => Breakpoint::new(mate_contig, mate_position, Orientation::Forward),
+                    _ => Breakpoint::new(
+                        mate_contig,
+                        mate_position.saturating_sub(1),
+                        Orientation::Reverse,
+                    ),
+                };
+                let to = Breakpoint::new(contig, position.saturating_sub(1), Orientation::Forward);
+
+                (from, to, inserted)
+            } else {
+                return Err(malformed(alt));
+            };
+
+            breakends.push(Breakend {
+                id: record.id.first().cloned(),
+                mate: info(record, "MATEID").map(String::from),
+                from,
+                to,
+                inserted: inserted.to_owned(),
+            });
+        }
+
+        Ok(breakends)
+    }
+
+    // Whether `other` joins the same breakpoints, maybe from the other end
+    pub fn same_adjacency(&self, other: &Breakend) -> bool {
+        (self.from == other.from && self.to == other.to)
+            || (self.from == other.to.flip() && self.to == other.from.flip())
+    }
+}
+
+/// Whether a record has breakend alleles
+pub fn has_breakends(record: &VCFRecord) -> bool {
+    record.alternative.iter().any(|alt| is_breakend(alt))
+}
+
+// Whether an allele is a breakend, with a mate like `G]chr1:10]` or without one like `.G`
+fn is_breakend(allele: &str) -> bool {
+    allele.contains(&['[', ']'][..])
+        || (allele.len() > 1 && (allele.starts_with('.') || allele.ends_with('.')))
+}
+
+// The type of a symbolic allele such as DUP for `<DUP:TANDEM>`, None for a sequence
+fn symbolic(allele: &str) -> Option<&str> {
+    if allele.starts_with('<') && allele.ends_with('>') {
+        allele[1..allele.len() - 1].split(':').next()
+    } else {
+        None
+    }
+}
+
+// The first value of an INFO field of a record
+fn info<'a>(record: &'a VCFRecord, key: &str) -> Option<&'a str> {
+    record.info.get(key)?.first().map(|value| value.as_str())
+}
+
+// 0 based position just past a structural variant that starts at `start`
+fn end_of(record: &VCFRecord, start: usize) -> Result<usize, Error> {
+    let malformed = |field: &str| {
+        Error::Malformed(format!(
+            "bad {} for the record at {}:{}",
+            field, record.chromosome, record.position
+        ))
+    };
+
+    // END is the 1 based position of the last base
+    let end = match (info(record, "END"), info(record, "SVLEN")) {
+        (Some(end), _) => end.parse::<usize>().map_err(|_| malformed("END"))?,
+        (None, Some(length)) => {
+            let length = length.parse::<i64>().map_err(|_| malformed("SVLEN"))?;
+            start + length.unsigned_abs() as usize
+        }
+        (None, None) => return Err(malformed("END or SVLEN")),
+    };
+
+    if end < start {
+        Err(malformed("END"))
+    } else {
+        Ok(end)
+    }
+}
+
+/// What to do with a record whose reference allele doesn't match the reference
+/// or that is outside of the sequence it's on
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum RefCheck {
+    // Stop at the first such record
+    #[default]
+    Error,
+    // Report the record and leave it out
+    Skip,
+    // Report the record and keep it if we can.
+    // The reference nodes follow the reference rather than the record.
+    Warn,
+}
+
+impl RefCheck {
+    // Whether to keep a record given the result of checking it with `check_reference`.
+    // What's wrong with a record we don't stop at goes in `warnings`.
+    pub fn keep(
+        self,
+        checked: Result<(), Error>,
+        warnings: &mut Vec<Error>,
+    ) -> Result<bool, Error> {
+        let e = match checked {
+            Ok(()) => return Ok(true),
+            Err(e) => e,
+        };
+
+        // A record outside of its sequence can't be kept
+        let keep = match self {
+            RefCheck::Error => return Err(e),
+            RefCheck::Skip => false,
+            RefCheck::Warn => matches!(e, Error::RefMismatch { .. }),
+        };
+
+        warnings.push(e);
+        Ok(keep)
+    }
+}
+
+impl std::str::FromStr for RefCheck {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(RefCheck::Error),
+            "skip" => Ok(RefCheck::Skip),
+            "warn" => Ok(RefCheck::Warn),
+            _ => Err(Error::Malformed(format!(
+                "{} isn't one of error, skip or warn",
+                s
+            ))),
+        }
+    }
+}
+
+/// Check that the reference allele of a record is `found`, the reference at its position.
+/// `found` is `None` when the reference allele doesn't fit on the sequence.
+/// The case of the bases doesn't matter since soft masked references are lower case.
+pub fn check_reference(record: &VCFRecord, found: Option<&[u8]>) -> Result<(), Error> {
+    match found {
+        Some(found) if record.position > 0 => {
+            if found.eq_ignore_ascii_case(record.reference.as_bytes()) {
+                Ok(())
+            } else {
+                Err(Error::RefMismatch {
+                    contig: record.chromosome.clone(),
+                    position: record.position,
+                    expected: String::from_utf8_lossy(found).into_owned(),
+                    found: record.reference.clone(),
+                })
+            }
+        }
+        _ => Err(Error::OutOfRange {
+            contig: record.chromosome.clone(),
+            position: record.position,
+        }),
+    }
+}
+
+/// Read all the records in the VCF
+pub fn gen_variations<R: Read>(
+    vcf_reader: &mut VCFReader<BufReader<R>>,
+) -> Result<Vec<VCFRecord>, Error> {
+    vcf_reader
+        .iter()
+        .map(|record| record.map_err(Error::from))
+        .collect()
+}
+
+/// Read all the records in the VCF grouped by chromosome and ordered by position.
+///
+/// Grouping frees us from relying on the order of chromosomes in the VCF
+/// being the same as the order of sequences in the reference.
+pub fn group_variations<R: Read>(
+    vcf_reader: &mut VCFReader<BufReader<R>>,
+) -> Result<HashMap<String, Vec<VCFRecord>>, Error> {
+    let mut groups: HashMap<String, Vec<VCFRecord>> = HashMap::new();
+
+    for result_record in vcf_reader.iter() {
+        let record = result_record?;
+        groups
+            .entry(record.chromosome.clone())
+            .or_default()
+            .push(record);
+    }
+
+    // The sort is stable so records at the same position keep their order in the VCF
+    for records in groups.values_mut() {
+        records.sort_by_key(|record| record.position);
+    }
+
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_can_gen_variations() {
+        let vcf_data = Cursor::new(
+            "
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+chr1	10	rs21549	T	C	.	PASS	.
+chr2	33	rs34146	G	C	.	PASS	.
+",
+        );
+        let mut vcf_reader = VCFReader::new(vcf_data).unwrap();
+        let variations = gen_variations(&mut vcf_reader).unwrap();
+
+        assert_eq!(variations.len(), 2);
+        assert_eq!(variations[1].chromosome, "chr2");
+    }
+
+    // The start, reference and alternative alleles of each record
+    fn alleles(records: &str) -> Vec<(usize, String, Vec<String>)> {
+        let vcf_data = Cursor::new(format!(
+            "##fileformat=VCFv4.2\n#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n{}",
+            records
+        ));
+        let mut vcf_reader = VCFReader::new(vcf_data).unwrap();
+
+        gen_variations(&mut vcf_reader)
+            .unwrap()
+            .iter()
+            .map(Alleles::new)
+            .map(|a| (a.start, a.reference, a.alternatives))
+            .collect()
+    }
+
+    #[test]
+    fn test_alleles() {
+        let alleles = alleles(
+            "\
+chr1	10	snv	T	C,G,C	.	PASS	.
+chr1	20	none	T	.	.	PASS	.
+chr1	30	del	TAC	T	.	PASS	.
+chr1	40	ins	A	ACC,AT	.	PASS	.
+chr1	1	start	CA	A	.	PASS	.
+chr1	50	mixed	A	T,TAC	.	PASS	.
+chr1	60	spanned	ATC	A,*	.	PASS	.
+",
+        );
+        let owned = |alleles: &[&str]| -> Vec<String> {
+            alleles.iter().map(|allele| String::from(*allele)).collect()
+        };
+
+        assert_eq!(alleles[0], (9, String::from("T"), owned(&["C", "G"])));
+        assert_eq!(alleles[1], (19, String::from("T"), owned(&[])));
+        assert_eq!(alleles[2], (30, String::from("AC"), owned(&[""])));
+        assert_eq!(alleles[3], (40, String::new(), owned(&["CC", "T"])));
+        assert_eq!(alleles[4], (0, String::from("C"), owned(&[""])));
+
+        // Alleles that don't share a base are kept as they are
+        assert_eq!(alleles[5], (49, String::from("A"), owned(&["T", "TAC"])));
+
+        // A spanning deletion is the allele of another record
+        assert_eq!(alleles[6], (60, String::from("TC"), owned(&[""])));
+    }
+
+    #[test]
+    fn test_structural_variants() {
+        let vcf_data = Cursor::new(
+            "
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+chr1	10	del	A	<DEL>,<INV>	.	PASS	END=20
+chr1	10	dup	A	<DUP:TANDEM>,<CNV>	.	PASS	SVLEN=-5
+chr1	10	ins	A	<INS>,AC	.	PASS	SVINSSEQ=GGT
+chr1	10	unresolved	A	<INS:ME>	.	PASS	SVLEN=300
+chr1	10	no_end	A	<DEL>	.	PASS	.
+",
+        );
+        let mut vcf_reader = VCFReader::new(vcf_data).unwrap();
+        let records = gen_variations(&mut vcf_reader).unwrap();
+
+        assert_eq!(
+            StructuralVariant::new(&records[0]).unwrap(),
+            vec![
+                StructuralVariant::Deletion { start: 10, end: 20 },
+                StructuralVariant::Inversion { start: 10, end: 20 }
+            ]
+        );
+        assert_eq!(
+            StructuralVariant::new(&records[1]).unwrap(),
+            vec![StructuralVariant::Duplication { start: 10, end: 15 }]
+        );
+
+        let insertion = StructuralVariant::new(&records[2]).unwrap();
+        assert_eq!(
+            insertion,
+            vec![StructuralVariant::Insertion {
+                start: 10,
+                sequence: String::from("GGT")
+            }]
+        );
+        assert_eq!(insertion[0].span(), (10, 10));
+        assert_eq!(
+            insertion[0].alleles(""),
+            Some(Alleles {
+                start: 10,
+                reference: String::new(),
+                alternatives: vec![String::from("GGT")]
+            })
+        );
+
+        // Symbolic alleles aren't sequences
+        assert_eq!(
+            Alleles::new(&records[2]).alternatives,
+            vec![String::from("C")]
+        );
+
+        match StructuralVariant::new(&records[3]) {
+            Err(Error::Unsupported(_)) => (),
+            _ => panic!("Expected an unsupported input error"),
+        }
+
+        match StructuralVariant::new(&records[4]) {
+            Err(Error::Malformed(_)) => (),
+            _ => panic!("Expected a malformed input error"),
+        }
+    }
+
+    #[test]
+    fn test_breakends() {
+        // The example of the VCF specification, each record is the mate of another
+        let vcf_data = Cursor::new(
+            "
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+2	321681	bnd_W	G	G]17:198982]	.	PASS	MATEID=bnd_Y
+2	321682	bnd_V	T	]13:123456]T	.	PASS	MATEID=bnd_U
+13	123456	bnd_U	C	C[2:321682[	.	PASS	MATEID=bnd_V
+13	123457	bnd_X	A	[17:198983[A	.	PASS	MATEID=bnd_Z
+17	198982	bnd_Y	A	A]2:321681]	.	PASS	MATEID=bnd_W
+17	198983	bnd_Z	C	[13:123457[C	.	PASS	MATEID=bnd_X
+2	10	inserted	G	GAC[2:5[,]2:20]TTG	.	PASS	.
+2	10	single	G	G.,.G	.	PASS	.
+2	10	unclosed	G	G[2:5	.	PASS	.
+",
+        );
+        let mut vcf_reader = VCFReader::new(vcf_data).unwrap();
+        let records = gen_variations(&mut vcf_reader).unwrap();
+        let breakends: Vec<Vec<Breakend>> = records[..8]
+            .iter()
+            .map(|record| Breakend::new(record).unwrap())
+            .collect();
+        let breakpoint = |contig: &str, position: usize, orientation: Orientation| Breakpoint {
+            contig: String::from(contig),
+            position,
+            orientation,
+        };
+
+        assert_eq!(
+            breakends[0],
+            vec![Breakend {
+                id: Some(String::from("bnd_W")),
+                mate: Some(String::from("bnd_Y")),
+                from: breakpoint("2", 321681, Orientation::Forward),
+                to: breakpoint("17", 198982, Orientation::Reverse),
+                inserted: String::new(),
+            }]
+        );
+        assert_eq!(
+            (&breakends[1][0].from, &breakends[1][0].to),
+            (
+                &breakpoint("13", 123456, Orientation::Forward),
+                &breakpoint("2", 321681, Orientation::Forward)
+            )
+        );
+        assert_eq!(
+            (&breakends[3][0].from, &breakends[3][0].to),
+            (
+                &breakpoint("17", 198982, Orientation::Reverse),
+                &breakpoint("13", 123456, Orientation::Forward)
+            )
+        );
+
+        // W and Y, V and U, X and Z are mates
+        for (x, y) in &[(0, 4), (1, 2), (3, 5)] {
+            assert!(breakends[*x][0].same_adjacency(&breakends[*y][0]));
+        }
+        assert!(!breakends[0][0].same_adjacency(&breakends[1][0]));
+
+        // The bases other than the reference are inserted
+        let inserted: Vec<&str> = breakends[6].iter().map(|b| b.inserted.as_str()).collect();
+        assert_eq!(inserted, vec!["AC", "TT"]);
+        assert_eq!(Alleles::new(&records[6]).alternatives, Vec::<String>::new());
+
+        assert!(breakends[7].is_empty());
+        assert!(has_breakends(&records[7]));
+
+        match Breakend::new(&records[8]) {
+            Err(Error::Malformed(_)) => (),
+            _ => panic!("Expected a malformed input error"),
+        }
+    }
+
+    #[test]
+    fn test_replacements_and_genotypes() {
+        let vcf_data = Cursor::new(
+            "
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO	FORMAT	S1	S2	S3
+chr1	10	ins	A	AC,.,<INV>	.	PASS	END=20	GT	0|1	1/0	1/1
+chr1	20	del	TAC	T,<DEL>	.	PASS	SVLEN=-2	GT:DP	.|2	1	./.
+chr1	30	none	T	.	.	PASS	.	DP	3	4	5
+chr1	40	bad	T	C	.	PASS	.	GT	0|x	0	0
+chr1	50	spanned	G	C,*	.	PASS	.	GT	1|2	0	0
+",
+        );
+        let mut vcf_reader = VCFReader::new(vcf_data).unwrap();
+        let records = gen_variations(&mut vcf_reader).unwrap();
+        let replacement = |start: usize, end: usize, sequence: &str| {
+            Some(Replacement {
+                start,
+                end,
+                sequence: String::from(sequence),
+            })
+        };
+
+        assert_eq!(
+            replacements(&records[0]).unwrap(),
+            vec![replacement(10, 10, "C"), None, None]
+        );
+        assert_eq!(
+            replacements(&records[1]).unwrap(),
+            vec![replacement(20, 22, ""), replacement(20, 22, "")]
+        );
+        assert_eq!(
+            replacements(&records[4]).unwrap(),
+            vec![replacement(49, 50, "C"), None]
+        );
+
+        let genotypes: Vec<Option<Genotype>> = ["S1", "S2", "S3"]
+            .iter()
+            .map(|sample| Genotype::new(&records[0], sample).unwrap())
+            .collect();
+        let phased: Vec<bool> = genotypes.iter().flatten().map(|g| g.phased).collect();
+        assert_eq!(
+            genotypes[0].as_ref().unwrap().alleles,
+            vec![Some(0), Some(1)]
+        );
+        assert_eq!(phased, vec![true, false, true]);
+
+        // Missing alleles and haploid genotypes
+        let genotypes: Vec<Option<Genotype>> = ["S1", "S2", "S3"]
+            .iter()
+            .map(|sample| Genotype::new(&records[1], sample).unwrap())
+            .collect();
+        assert_eq!(genotypes[0].as_ref().unwrap().alleles, vec![None, Some(2)]);
+        assert_eq!(genotypes[1].as_ref().unwrap().alleles, vec![Some(1)]);
+        assert_eq!(genotypes[2].as_ref().unwrap().alleles, vec![None, None]);
+
+        assert_eq!(Genotype::new(&records[2], "S1").unwrap(), None);
+
+        match Genotype::new(&records[3], "S1") {
+            Err(Error::Malformed(_)) => (),
+            _ => panic!("Expected a malformed input error"),
+        }
+    }
+
+    #[test]
+    fn test_sites() {
+        let allele = |start: usize, reference: &str, alternatives: &[&str]| Alleles {
+            start,
+            reference: String::from(reference),
+            alternatives: alternatives.iter().map(|a| String::from(*a)).collect(),
+        };
+        let sequence = b"GATCATCGGTTACACAGCTA";
+
+        let sites = sites(
+            vec![
+                allele(12, "C", &["T"]),
+                allele(4, "ATCG", &[""]),
+                allele(6, "CG", &["AC"]),
+                allele(4, "A", &["G", "T"]),
+                allele(10, "", &["GG"]),
+                allele(10, "T", &["A"]),
+                allele(8, "G", &["C"]),
+            ],
+            &Window::whole(sequence),
+        );
+
+        assert_eq!(
+            sites,
+            vec![
+                allele(4, "ATCG", &["", "GTCG", "TTCG", "ATAC"]),
+                allele(8, "G", &["C"]),
+                allele(10, "T", &["GGT", "A"]),
+                allele(12, "C", &["T"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_reference() {
+        let vcf_data = Cursor::new(
+            "
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+chr1	3	rs1	TC	T	.	PASS	.
+chr1	0	rs2	T	C	.	PASS	.
+",
+        );
+        let mut vcf_reader = VCFReader::new(vcf_data).unwrap();
+        let records = gen_variations(&mut vcf_reader).unwrap();
+
+        assert!(check_reference(&records[0], Some(b"tc")).is_ok());
+
+        let mismatch = check_reference(&records[0], Some(b"TA"));
+        match mismatch {
+            Err(Error::RefMismatch {
+                ref contig,
+                position,
+                ref expected,
+                ref found,
+            }) => assert_eq!(
+                (contig.as_str(), position, expected.as_str(), found.as_str()),
+                ("chr1", 3, "TA", "TC")
+            ),
+            _ => panic!("Expected a reference mismatch error"),
+        }
+
+        // Only mismatches can be kept
+        let mut warnings: Vec<Error> = Vec::new();
+        assert!(!RefCheck::Skip.keep(mismatch, &mut warnings).unwrap());
+        assert!(RefCheck::Warn
+            .keep(check_reference(&records[0], Some(b"TA")), &mut warnings)
+            .unwrap());
+        assert!(!RefCheck::Warn
+            .keep(check_reference(&records[0], None), &mut warnings)
+            .unwrap());
+        assert!(RefCheck::Error
+            .keep(check_reference(&records[0], None), &mut warnings)
+            .is_err());
+        assert!(RefCheck::Warn
+            .keep(check_reference(&records[0], Some(b"TC")), &mut warnings)
+            .unwrap());
+
+        // Those we don't stop at are warnings
+        assert_eq!(warnings.len(), 3);
+        assert!(matches!(warnings[2], Error::OutOfRange { .. }));
+
+        match check_reference(&records[1], Some(b"")) {
+            Err(Error::OutOfRange { position: 0, .. }) => (),
+            _ => panic!("Expected an out of range error"),
+        }
+    }
+
+    #[test]
+    fn test_can_group_variations() {
+        let vcf_data = Cursor::new(
+            "
+##fileformat=VCFv4.2
+#CHROM	POS	ID	REF	ALT	QUAL	FILTER	INFO
+chr2	33	rs34146	G	C	.	PASS	.
+chr1	20	rs21550	T	C	.	PASS	.
+chr2	12	rs34147	G	C	.	PASS	.
+chr1	10	rs21549	T	C	.	PASS	.
+",
+        );
+        let mut vcf_reader = VCFReader::new(vcf_data).unwrap();
+        let groups = group_variations(&mut vcf_reader).unwrap();
+
+        let positions =
+            |chr: &str| -> Vec<u64> { groups[chr].iter().map(|record| record.position).collect() };
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(positions("chr1"), vec![10, 20]);
+        assert_eq!(positions("chr2"), vec![12, 33]);
+    }
+}
@@ -8,12 +8,33 @@
 //! # Examples
 //!
 //! ```rust
-//! // Real example coming up
-//! assert_eq!(3,3);
+//! use std::io::Cursor;
+//!
+//! let reference = Cursor::new(">chr1\nGATCATCGGTTACACAGCTA\n");
+//! let vcf = Cursor::new(
+//!     "##fileformat=VCFv4.2\n\
+//! #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+//! chr1\t5\trs21549\tA\tG\t.\tPASS\t.\n",
+//! );
+//!
+//! let graph = flex_vg::construct(reference, vcf).unwrap();
+//! assert_eq!(graph.hashmap().len(), 4);
 //! ```
 
 mod io;
 
+pub mod error;
 pub mod graph;
 
+pub use error::Error;
+pub use graph::graph::ConstructOptions;
+pub use graph::handlegraph::{
+    DeletableHandleGraph, HandleGraph, MutableHandleGraph, MutablePathHandleGraph, PathHandleGraph,
+};
+pub use graph::types::{HashAlgorithm, HashScheme, PositionEncoding};
 pub use io::cli::start;
+pub use io::fasta::{FaiEntry, IndexedFasta};
+pub use io::filter::{merge_regions, read_bed, Comparison, InfoExpression, RecordFilter, Region};
+pub use io::reference::{construct, construct_file, construct_indexed, construct_with, open_vcf};
+pub use io::serialization::{deserialize_graph, serialize_graph};
+pub use io::variation::{gen_variations, RefCheck, Unphased};
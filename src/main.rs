@@ -1,7 +1,8 @@
-use std::error::Error;
+use std::process;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    flex_vg::start();
-
-    Ok(())
+fn main() {
+    if let Err(e) = flex_vg::start() {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
 }